@@ -223,6 +223,7 @@ async def _execute_sql(sql: str) -> dict:
         resp = await client.post(
             f"{SQL_BACKEND_URL}/api/sql",
             json={"query": sql},
+            headers={"X-Caller": "rag-service"},
         )
         resp.raise_for_status()
         return resp.json()
@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+/// Limits applied to queries run through `/api/sql`.
+#[derive(Clone)]
+pub struct SqlConfig {
+    /// Hard cap on rows returned by a single query; requests may ask for fewer.
+    pub max_rows: usize,
+    /// Time budget for the `COUNT(*)` that reports how many rows a truncated
+    /// result left out. The count is skipped when it runs over.
+    pub count_timeout_ms: u64,
+    /// Statement timeout used when a request doesn't ask for one.
+    pub default_timeout_ms: u64,
+    /// Upper bound on the statement timeout a request may ask for.
+    pub max_timeout_ms: u64,
+    /// How long a paged query's continuation token stays valid.
+    pub cursor_ttl_secs: u64,
+    /// Queries the planner expects to cost more than this are refused before
+    /// they run, in Postgres cost units.
+    pub guard_max_cost: f64,
+    /// Queries the planner expects to return more rows than this are refused.
+    pub guard_max_rows: f64,
+    /// Tables with at least this many rows are called out by name when a
+    /// refused query reads them with a sequential scan.
+    pub guard_large_table_rows: f64,
+    /// How long a cached `/api/sql` result is served again; 0 disables the
+    /// cache.
+    pub cache_ttl_secs: u64,
+    /// Memory budget for cached results, measured as serialized JSON.
+    pub cache_max_bytes: usize,
+}
+
+impl SqlConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_rows: env_or("SQL_MAX_ROWS", 1000),
+            count_timeout_ms: env_or("SQL_COUNT_TIMEOUT_MS", 500),
+            default_timeout_ms: env_or("SQL_DEFAULT_TIMEOUT_MS", 5_000),
+            max_timeout_ms: env_or("SQL_MAX_TIMEOUT_MS", 30_000),
+            cursor_ttl_secs: env_or("SQL_CURSOR_TTL_SECS", 300),
+            guard_max_cost: env_or("SQL_GUARD_MAX_COST", 1_000_000.0),
+            guard_max_rows: env_or("SQL_GUARD_MAX_ROWS", 1_000_000.0),
+            guard_large_table_rows: env_or("SQL_GUARD_LARGE_TABLE_ROWS", 100_000.0),
+            cache_ttl_secs: env_or("SQL_CACHE_TTL_SECS", 30),
+            cache_max_bytes: env_or("SQL_CACHE_MAX_BYTES", 64 * 1024 * 1024),
+        }
+    }
+}
+
+/// Settings for the schema endpoints that ground LLM prompts.
+#[derive(Clone)]
+pub struct SchemaConfig {
+    /// How long computed column statistics are served before being rebuilt.
+    pub stats_refresh_secs: u64,
+    /// `?refresh=true` is ignored until the statistics are at least this old,
+    /// so clients can't keep the database busy rebuilding them.
+    pub stats_min_refresh_secs: u64,
+    /// Statement timeout for the queries that compute statistics.
+    pub stats_timeout_ms: u64,
+    /// Tables with more rows than this are sampled down to roughly this many
+    /// rows when statistics have to be computed by query.
+    pub stats_sample_rows: f64,
+    /// Text columns with at most this many distinct values list their most
+    /// frequent values.
+    pub stats_max_distinct_for_top: f64,
+    /// How many frequent values to list per column.
+    pub stats_top_values: usize,
+}
+
+impl SchemaConfig {
+    pub fn from_env() -> Self {
+        Self {
+            stats_refresh_secs: env_or("SCHEMA_STATS_REFRESH_SECS", 600),
+            stats_min_refresh_secs: env_or("SCHEMA_STATS_MIN_REFRESH_SECS", 60),
+            stats_timeout_ms: env_or("SCHEMA_STATS_TIMEOUT_MS", 10_000),
+            stats_sample_rows: env_or("SCHEMA_STATS_SAMPLE_ROWS", 100_000.0),
+            stats_max_distinct_for_top: env_or("SCHEMA_STATS_MAX_DISTINCT_FOR_TOP", 50.0),
+            stats_top_values: env_or("SCHEMA_STATS_TOP_VALUES", 10),
+        }
+    }
+}
+
+/// Settings for the LLM behind `/api/nl2sql`.
+#[derive(Clone)]
+pub struct LlmConfig {
+    /// `openai` for any OpenAI-compatible chat completions API, or `mock`
+    /// for the deterministic offline provider.
+    pub provider: String,
+    /// API root including the version, e.g. `https://api.openai.com/v1`.
+    pub base_url: String,
+    /// Sent as a bearer token when set; local servers often need none.
+    pub api_key: Option<String>,
+    pub model: String,
+    pub timeout_secs: u64,
+    /// Upper bound on how many times a failing generated query is sent back
+    /// to the model for repair.
+    pub max_repairs: u32,
+    /// JSON file mapping question substrings to the SQL the mock provider
+    /// answers with.
+    pub mock_fixtures: Option<String>,
+}
+
+impl LlmConfig {
+    pub fn from_env() -> Self {
+        Self {
+            provider: env_or("LLM_PROVIDER", "openai".to_string()),
+            base_url: env_or("LLM_BASE_URL", "https://api.openai.com/v1".to_string()),
+            api_key: std::env::var("LLM_API_KEY").ok().filter(|k| !k.is_empty()),
+            model: env_or("LLM_MODEL", "gpt-4o-mini".to_string()),
+            timeout_secs: env_or("LLM_TIMEOUT_SECS", 60),
+            max_repairs: env_or("LLM_MAX_REPAIRS", 2),
+            mock_fixtures: std::env::var("LLM_MOCK_FIXTURES").ok(),
+        }
+    }
+}
+
+/// `API_DEBUG_ERRORS=true` adds the underlying cause, such as the driver's
+/// message, to error responses. Off by default so internals don't leak.
+pub fn debug_errors() -> bool {
+    env_or("API_DEBUG_ERRORS", false)
+}
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(raw) => raw
+            .parse()
+            .unwrap_or_else(|_| panic!("{} has an invalid value '{}'", key, raw)),
+        Err(_) => default,
+    }
+}
@@ -1,24 +1,340 @@
+use std::sync::OnceLock;
+
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
-use serde_json::json;
+use serde::{Deserialize, Serialize};
+use sqlx::error::DatabaseError;
+use sqlx::postgres::PgDatabaseError;
+
+static DEBUG: OnceLock<bool> = OnceLock::new();
+
+/// Lets error responses include `detail`; set once at startup from
+/// `API_DEBUG_ERRORS`.
+pub fn set_debug(enabled: bool) {
+    let _ = DEBUG.set(enabled);
+}
+
+fn debug() -> bool {
+    DEBUG.get().copied().unwrap_or(false)
+}
+
+/// Declares `ErrorCode` with each variant's wire name written once, for
+/// both serde and `as_str`.
+macro_rules! error_codes {
+    ($($variant:ident = $value:literal),+ $(,)?) => {
+        /// Stable, machine-readable error identifiers, sent as `code`. Clients
+        /// should match on these rather than on the message.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+        pub enum ErrorCode {
+            $(
+                #[serde(rename = $value)]
+                $variant,
+            )+
+        }
+
+        impl ErrorCode {
+            #[cfg(test)]
+            const ALL: &'static [ErrorCode] = &[$(ErrorCode::$variant),+];
+
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$variant => $value,)+
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    BadRequest = "bad_request",
+    ValidationFailed = "validation_failed",
+    NotFound = "not_found",
+    Conflict = "conflict",
+    UniqueViolation = "unique_violation",
+    ForeignKeyViolation = "foreign_key_violation",
+    CheckViolation = "check_violation",
+    NotNullViolation = "not_null_violation",
+    InvalidValue = "invalid_value",
+    ForbiddenStatement = "forbidden_statement",
+    InvalidRequest = "invalid_request",
+    InvalidParams = "invalid_params",
+    QueryFailed = "query_failed",
+    QueryTimeout = "query_timeout",
+    QueryTooExpensive = "query_too_expensive",
+    CursorExpired = "cursor_expired",
+    CursorMismatch = "cursor_mismatch",
+    LlmFailed = "llm_failed",
+    Unavailable = "unavailable",
+    InternalError = "internal_error",
+}
+
+impl ErrorCode {
+    /// The status an error with this code is normally sent with.
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::BadRequest
+            | ErrorCode::InvalidRequest
+            | ErrorCode::InvalidParams
+            | ErrorCode::QueryFailed
+            | ErrorCode::CursorMismatch => StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed
+            | ErrorCode::CheckViolation
+            | ErrorCode::NotNullViolation
+            | ErrorCode::InvalidValue
+            | ErrorCode::ForeignKeyViolation
+            | ErrorCode::QueryTooExpensive => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict | ErrorCode::UniqueViolation => StatusCode::CONFLICT,
+            ErrorCode::ForbiddenStatement => StatusCode::FORBIDDEN,
+            ErrorCode::QueryTimeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::CursorExpired => StatusCode::GONE,
+            ErrorCode::LlmFailed => StatusCode::BAD_GATEWAY,
+            ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// A problem with one field of the request body.
+#[derive(Clone, Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+/// An error response:
+/// `{"error", "code", "sqlstate"?, "constraint"?, "fields"?, "detail"?}`.
+#[derive(Debug)]
+pub struct AppError {
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    pub message: String,
+    /// Postgres SQLSTATE when the error came from the database.
+    pub sqlstate: Option<Box<str>>,
+    /// Name of the violated constraint.
+    pub constraint: Option<Box<str>>,
+    pub fields: Vec<FieldError>,
+    /// What went wrong underneath, e.g. the driver's message or the row
+    /// that failed a check. Only sent when `API_DEBUG_ERRORS` is set.
+    pub detail: Option<Box<str>>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+    code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sqlstate: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constraint: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    fields: &'a [FieldError],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            status: code.status(),
+            code,
+            message: message.into(),
+            sqlstate: None,
+            constraint: None,
+            fields: vec![],
+            detail: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::BadRequest, message)
+    }
 
-pub struct AppError(pub StatusCode, pub String);
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    /// A 422 listing every field that failed validation.
+    pub fn validation(fields: Vec<FieldError>) -> Self {
+        let message = match fields.as_slice() {
+            [only] => format!("{}: {}", only.field, only.message),
+            _ => format!("{} fields failed validation", fields.len()),
+        };
+        Self { fields, ..Self::new(ErrorCode::ValidationFailed, message) }
+    }
+
+    /// A 500 whose cause is only shown in debug mode.
+    pub fn internal(detail: impl Into<String>) -> Self {
+        Self {
+            detail: Some(detail.into().into()),
+            ..Self::new(ErrorCode::InternalError, "Internal server error")
+        }
+    }
+
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    /// Classifies a Postgres error by SQLSTATE, naming the constraint and,
+    /// where the server reports it, the offending columns.
+    fn from_database(db: &dyn DatabaseError) -> Self {
+        let sqlstate = db.code().map(|c| c.into_owned()).unwrap_or_default();
+        let pg = db.try_downcast_ref::<PgDatabaseError>();
+        let table = pg.and_then(|e| e.table()).unwrap_or_default();
+        let key_detail = pg.and_then(|e| e.detail()).unwrap_or_default();
+
+        let mut err = match sqlstate.as_str() {
+            "23505" => {
+                let columns = key_columns(key_detail);
+                let fields = columns.iter().map(|c| FieldError::new(*c, "already exists")).collect();
+                let message = match columns.as_slice() {
+                    [] => format!("Duplicate key in {}", table),
+                    cols => format!("{} already exists in {}", cols.join(", "), table),
+                };
+                Self { fields, ..Self::new(ErrorCode::UniqueViolation, message) }
+            }
+            "23503" => {
+                // Deleting a row others point at conflicts with existing
+                // data; pointing at a row that doesn't exist is bad input.
+                if let Some((_, referrer)) = key_detail.split_once("is still referenced from table ") {
+                    let referrer = referrer.trim_end_matches('.').trim_matches('"');
+                    Self::new(ErrorCode::ForeignKeyViolation, format!("Row is still referenced from {}", referrer))
+                        .with_status(StatusCode::CONFLICT)
+                } else {
+                    let target = key_detail
+                        .split_once("is not present in table ")
+                        .map_or("", |(_, t)| t.trim_end_matches('.').trim_matches('"'));
+                    let fields = key_columns(key_detail)
+                        .into_iter()
+                        .map(|c| FieldError::new(c, format!("refers to a {} row that doesn't exist", target)))
+                        .collect();
+                    Self {
+                        fields,
+                        ..Self::new(ErrorCode::ForeignKeyViolation, "A referenced row doesn't exist")
+                    }
+                }
+            }
+            "23514" => {
+                let constraint = db.constraint().unwrap_or_default();
+                let fields = check_column(table, constraint)
+                    .map(|c| vec![FieldError::new(c, format!("violates {}", constraint))])
+                    .unwrap_or_default();
+                Self {
+                    fields,
+                    ..Self::new(ErrorCode::CheckViolation, format!("Value violates check constraint {}", constraint))
+                }
+            }
+            "23502" => {
+                let column = pg.and_then(|e| e.column()).unwrap_or_default();
+                Self {
+                    fields: vec![FieldError::new(column, "must not be null")],
+                    ..Self::new(ErrorCode::NotNullViolation, format!("{} must not be null", column))
+                }
+            }
+            "57014" => Self::new(ErrorCode::QueryTimeout, "Statement cancelled after exceeding its timeout"),
+            code if code.starts_with("23") => Self::new(ErrorCode::Conflict, "Conflicts with existing data"),
+            // Data exceptions (bad casts, out-of-range numbers, strings too
+            // long for the column) describe the input, not the server.
+            code if code.starts_with("22") => Self::new(ErrorCode::InvalidValue, db.message()),
+            _ => Self::internal(""),
+        };
+
+        err.sqlstate = (!sqlstate.is_empty()).then(|| sqlstate.into());
+        err.constraint = db.constraint().map(Into::into);
+        err.detail = Some(match pg.and_then(|e| e.detail()) {
+            Some(detail) => format!("{}: {}", db.message(), detail).into(),
+            None => db.message().into(),
+        });
+        err
+    }
+}
+
+/// Columns named in a key violation's detail, e.g.
+/// `Key (category_id)=(99) is not present in table "vehicle_categories".`
+fn key_columns(detail: &str) -> Vec<&str> {
+    detail
+        .strip_prefix("Key (")
+        .and_then(|rest| rest.split_once(")="))
+        .map(|(cols, _)| cols.split(", ").collect())
+        .unwrap_or_default()
+}
+
+/// The column a check constraint guards, when it has Postgres' default
+/// `<table>_<column>_check` name.
+fn check_column<'a>(table: &str, constraint: &'a str) -> Option<&'a str> {
+    constraint
+        .strip_prefix(table)?
+        .strip_prefix('_')?
+        .strip_suffix("_check")
+        .filter(|c| !c.is_empty())
+}
+
+impl AppError {
+    /// The status and body this error is sent with, for handlers that build
+    /// JSON responses themselves.
+    pub fn into_json(self) -> (StatusCode, Json<serde_json::Value>) {
+        if self.status.is_server_error() {
+            tracing::error!(
+                "{} ({}): {}",
+                self.code.as_str(),
+                self.message,
+                self.detail.as_deref().unwrap_or_default()
+            );
+        }
+
+        let body = ErrorBody {
+            error: &self.message,
+            code: self.code,
+            sqlstate: self.sqlstate.as_deref(),
+            constraint: self.constraint.as_deref(),
+            fields: &self.fields,
+            detail: self.detail.as_deref().filter(|d| debug() && !d.is_empty()),
+        };
+        (self.status, Json(serde_json::json!(body)))
+    }
+}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
-        let body = Json(json!({ "error": self.1 }));
-        (self.0, body).into_response()
+        self.into_json().into_response()
     }
 }
 
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
         match err {
-            sqlx::Error::RowNotFound => {
-                AppError(StatusCode::NOT_FOUND, "Not found".into())
+            sqlx::Error::RowNotFound => AppError::not_found("Not found"),
+            sqlx::Error::Database(db) => AppError::from_database(db.as_ref()),
+            sqlx::Error::PoolTimedOut => {
+                Self { detail: Some(err.to_string().into()), ..Self::new(ErrorCode::Unavailable, "Database is busy") }
             }
-            _ => AppError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            _ => AppError::internal(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_serialize_as_their_names() {
+        for &code in ErrorCode::ALL {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+            assert_eq!(serde_json::from_value::<ErrorCode>(code.as_str().into()).unwrap(), code);
         }
     }
 }
@@ -0,0 +1,137 @@
+//! Extractors whose rejections are `AppError`s, so a malformed request gets
+//! the same JSON error body as every other failure instead of axum's plain
+//! text.
+
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts, Path, Query, Request};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::Json;
+
+use crate::error::{AppError, ErrorCode};
+
+/// `Json<T>` for request bodies. A body that is missing, isn't JSON or
+/// doesn't fit `T` is answered with `invalid_request`, keeping the status
+/// axum picked (400, 413, 415 or 422) and its explanation.
+pub struct AppJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for AppJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(AppJson(value)),
+            Err(rejection) => Err(invalid(rejection.status(), rejection.body_text())),
+        }
+    }
+}
+
+/// `Path<T>` for URL segments, e.g. a non-numeric id. Rejections are
+/// `invalid_request`, with axum's status.
+pub struct AppPath<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for AppPath<T>
+where
+    Path<T>: FromRequestParts<S, Rejection = PathRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(AppPath(value)),
+            Err(rejection) => Err(invalid(rejection.status(), rejection.body_text())),
+        }
+    }
+}
+
+/// `Query<T>` for query strings. Rejections are `invalid_request`, with
+/// axum's status.
+pub struct AppQuery<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for AppQuery<T>
+where
+    Query<T>: FromRequestParts<S, Rejection = QueryRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Query::<T>::from_request_parts(parts, state).await {
+            Ok(Query(value)) => Ok(AppQuery(value)),
+            Err(rejection) => Err(invalid(rejection.status(), rejection.body_text())),
+        }
+    }
+}
+
+fn invalid(status: StatusCode, message: String) -> AppError {
+    AppError::new(ErrorCode::InvalidRequest, message).with_status(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Question {
+        question: String,
+    }
+
+    async fn extract(content_type: Option<&str>, body: &'static str) -> Result<Question, AppError> {
+        let mut req = Request::builder().method("POST").uri("/");
+        if let Some(content_type) = content_type {
+            req = req.header(CONTENT_TYPE, content_type);
+        }
+        let req = req.body(Body::from(body)).unwrap();
+        AppJson::<Question>::from_request(req, &()).await.map(|AppJson(q)| q)
+    }
+
+    #[tokio::test]
+    async fn accepts_json_bodies() {
+        let q = extract(Some("application/json"), r#"{"question": "How many?"}"#).await.unwrap();
+        assert_eq!(q.question, "How many?");
+    }
+
+    #[tokio::test]
+    async fn rejects_bodies_as_invalid_requests() {
+        let cases = [
+            (None, r#"{"question": "How many?"}"#, StatusCode::UNSUPPORTED_MEDIA_TYPE, "Content-Type"),
+            (Some("application/json"), r#"{"question": "#, StatusCode::BAD_REQUEST, "parse"),
+            (Some("application/json"), r#"{"q": "How many?"}"#, StatusCode::UNPROCESSABLE_ENTITY, "missing field `question`"),
+            (Some("application/json"), r#"{"question": 7}"#, StatusCode::UNPROCESSABLE_ENTITY, "invalid type"),
+        ];
+        for (content_type, body, status, message) in cases {
+            let err = extract(content_type, body).await.unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidRequest, "{}", body);
+            assert_eq!(err.status, status, "{}", body);
+            assert!(err.message.contains(message), "{}: {}", body, err.message);
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Refresh {
+        #[serde(default)]
+        refresh: bool,
+    }
+
+    #[tokio::test]
+    async fn rejects_query_strings_as_invalid_requests() {
+        let (mut parts, ()) = Request::builder().uri("/?refresh=maybe").body(()).unwrap().into_parts();
+        let err = AppQuery::<Refresh>::from_request_parts(&mut parts, &()).await.err().unwrap();
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(err.message.contains("refresh"), "{}", err.message);
+
+        let (mut parts, ()) = Request::builder().uri("/?refresh=true").body(()).unwrap().into_parts();
+        let AppQuery(q) = AppQuery::<Refresh>::from_request_parts(&mut parts, &()).await.unwrap();
+        assert!(q.refresh);
+    }
+}
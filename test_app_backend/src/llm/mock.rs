@@ -0,0 +1,145 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+
+use super::{LlmError, LlmProvider, Message, Role};
+use crate::config::LlmConfig;
+
+/// Answers without a model, so the NL-to-SQL flow can run offline and give
+/// the same output for the same input every time.
+///
+/// Questions containing a fixture's key (case-insensitive, first match in
+/// file order) get that fixture's SQL. Anything else is matched against the
+/// table names in the schema prompt: "how many ..." questions count the
+/// table, others select its first ten rows.
+///
+/// Only the latest user message is matched. In a repair round that message
+/// starts with the Postgres error, so a fixture keyed on the error text
+/// supplies the fix; otherwise the table named in the failing query is
+/// selected from.
+pub struct MockProvider {
+    fixtures: Vec<(String, String)>,
+}
+
+impl MockProvider {
+    pub fn new(fixtures: Vec<(String, String)>) -> Self {
+        Self {
+            fixtures: fixtures
+                .into_iter()
+                .map(|(pattern, sql)| (pattern.to_lowercase(), sql))
+                .collect(),
+        }
+    }
+
+    /// Loads fixtures from the JSON object in `LLM_MOCK_FIXTURES`, if set.
+    pub fn from_config(config: &LlmConfig) -> Result<Self, String> {
+        let Some(path) = &config.mock_fixtures else {
+            return Ok(Self::new(vec![]));
+        };
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read mock fixtures {}: {}", path, e))?;
+        let map: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid mock fixtures {}: {}", path, e))?;
+
+        let mut fixtures = vec![];
+        for (pattern, sql) in map {
+            let sql = sql
+                .as_str()
+                .ok_or_else(|| format!("Mock fixture '{}' in {} must be a string", pattern, path))?;
+            fixtures.push((pattern, sql.to_string()));
+        }
+        Ok(Self::new(fixtures))
+    }
+
+    fn answer(&self, messages: &[Message]) -> (Option<String>, String) {
+        let question = messages
+            .iter()
+            .rev()
+            .find(|m| matches!(m.role, Role::User))
+            .map(|m| m.content.to_lowercase())
+            .unwrap_or_default();
+
+        if let Some((pattern, sql)) = self.fixtures.iter().find(|(p, _)| question.contains(p.as_str())) {
+            return (Some(sql.clone()), format!("Fixture for '{}'", pattern));
+        }
+
+        let tables = messages
+            .iter()
+            .filter(|m| matches!(m.role, Role::System))
+            .flat_map(|m| schema_tables(&m.content))
+            .collect::<Vec<_>>();
+
+        // The earliest mention wins, and the longest name at that spot, so
+        // "vehicle categories" means vehicle_categories rather than vehicles.
+        let mentioned = tables
+            .iter()
+            .filter_map(|table| {
+                spellings(table)
+                    .into_iter()
+                    .filter_map(|s| question.find(s.as_str()).map(|at| (Reverse(at), s.len())))
+                    .max()
+                    .map(|rank| (rank, table))
+            })
+            .max_by_key(|(rank, _)| *rank);
+
+        match mentioned {
+            None => (None, "The question doesn't mention any table in the schema".to_string()),
+            Some((_, table)) => {
+                let counting = ["how many", "count", "number of"].iter().any(|w| question.contains(w));
+                if counting {
+                    (Some(format!("SELECT COUNT(*) AS count FROM {}", table)), format!("Counts {}", table))
+                } else {
+                    (Some(format!("SELECT * FROM {} LIMIT 10", table)), format!("First rows of {}", table))
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn name(&self) -> String {
+        "mock".to_string()
+    }
+
+    async fn complete(&self, messages: &[Message]) -> Result<String, LlmError> {
+        let (sql, explanation) = self.answer(messages);
+        Ok(serde_json::json!({ "sql": sql, "explanation": explanation }).to_string())
+    }
+}
+
+/// Table names from the `Tables:` section of a prompt rendered by
+/// `schema::render_prompt`, where each table starts an unindented line
+/// `name(col type, ...)`.
+fn schema_tables(prompt: &str) -> Vec<String> {
+    let Some((_, tables)) = prompt.split_once("Tables:") else {
+        return vec![];
+    };
+    tables
+        .lines()
+        .filter(|line| !line.starts_with(' '))
+        .filter_map(|line| line.split_once('(').map(|(name, _)| name))
+        .map(|name| name.trim_start_matches("view ").trim().to_string())
+        .filter(|name| !name.is_empty() && !name.contains(' '))
+        .collect()
+}
+
+/// Ways a question may refer to a table: `maintenance_records` also
+/// matches "maintenance records" and "maintenance record".
+fn spellings(table: &str) -> Vec<String> {
+    let name = table.rsplit('.').next().unwrap_or(table).to_lowercase();
+    let singular = if let Some(stem) = name.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else {
+        name.strip_suffix('s').unwrap_or(&name).to_string()
+    };
+
+    let mut spellings = vec![];
+    for s in [name, singular] {
+        spellings.push(s.replace('_', " "));
+        spellings.push(s);
+    }
+    spellings.sort();
+    spellings.dedup();
+    spellings
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::config::LlmConfig;
+
+mod mock;
+mod openai;
+
+pub use mock::MockProvider;
+pub use openai::OpenAiProvider;
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self { role: Role::System, content: content.into() }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { role: Role::User, content: content.into() }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self { role: Role::Assistant, content: content.into() }
+    }
+}
+
+#[derive(Debug)]
+pub struct LlmError(pub String);
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A chat model that turns a conversation into the assistant's next reply.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Identifies the provider and model in responses and logs.
+    fn name(&self) -> String;
+
+    async fn complete(&self, messages: &[Message]) -> Result<String, LlmError>;
+}
+
+/// Builds the provider selected by `LLM_PROVIDER`.
+pub fn from_config(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, String> {
+    match config.provider.as_str() {
+        "openai" => Ok(Arc::new(OpenAiProvider::new(config)?)),
+        "mock" => Ok(Arc::new(MockProvider::from_config(config)?)),
+        other => Err(format!("Unknown LLM_PROVIDER '{}': expected openai or mock", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(provider: &str, mock_fixtures: Option<String>) -> LlmConfig {
+        LlmConfig {
+            provider: provider.to_string(),
+            base_url: "http://localhost:1/v1".to_string(),
+            api_key: None,
+            model: "test-model".to_string(),
+            timeout_secs: 1,
+            max_repairs: 2,
+            mock_fixtures,
+        }
+    }
+
+    #[test]
+    fn selects_the_configured_provider() {
+        assert_eq!(from_config(&config("mock", None)).unwrap().name(), "mock");
+        assert_eq!(from_config(&config("openai", None)).unwrap().name(), "openai:test-model");
+        assert_eq!(
+            from_config(&config("claude", None)).err().as_deref(),
+            Some("Unknown LLM_PROVIDER 'claude': expected openai or mock")
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_answers_from_its_fixtures_file() {
+        let path = std::env::temp_dir().join(format!("llm-fixtures-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"Busiest Branch": "SELECT 42 AS answer"}"#).unwrap();
+        let provider = from_config(&config("mock", Some(path.display().to_string())));
+        std::fs::remove_file(&path).unwrap();
+
+        let reply = provider
+            .unwrap()
+            .complete(&[Message::user("Which is the busiest branch?")])
+            .await
+            .unwrap();
+        let reply: serde_json::Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["sql"], "SELECT 42 AS answer");
+    }
+
+    #[test]
+    fn missing_fixtures_file_is_a_config_error() {
+        let error = from_config(&config("mock", Some("/nonexistent/fixtures.json".to_string()))).err();
+        assert!(error.is_some_and(|e| e.starts_with("Could not read mock fixtures /nonexistent/fixtures.json")));
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{LlmError, LlmProvider, Message};
+use crate::config::LlmConfig;
+
+/// Longest slice of an error response body worth repeating to callers.
+const MAX_ERROR_BODY: usize = 500;
+
+/// Talks to any server implementing OpenAI's `POST /chat/completions`:
+/// OpenAI itself, Azure-style gateways, vLLM, Ollama, llama.cpp, ...
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+}
+
+#[derive(Deserialize)]
+struct ChoiceMessage {
+    content: Option<String>,
+}
+
+impl OpenAiProvider {
+    pub fn new(config: &LlmConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|e| format!("Could not build HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            url: format!("{}/chat/completions", config.base_url.trim_end_matches('/')),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> String {
+        format!("openai:{}", self.model)
+    }
+
+    async fn complete(&self, messages: &[Message]) -> Result<String, LlmError> {
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": messages,
+            "temperature": 0,
+        });
+
+        let mut request = self.client.post(&self.url).json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| LlmError(format!("Request to {} failed: {}", self.url, e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            let text: String = text.chars().take(MAX_ERROR_BODY).collect();
+            return Err(LlmError(format!("{} returned {}: {}", self.url, status, text)));
+        }
+
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LlmError(format!("Failed to read the response from {}: {}", self.url, e)))?;
+
+        completion(&text)
+            .map_err(|e| LlmError(format!("Unexpected response from {}: {}", self.url, e)))?
+            .ok_or_else(|| LlmError(format!("{} returned no completion", self.url)))
+    }
+}
+
+/// The first choice's message from a chat completions response body.
+fn completion(body: &str) -> Result<Option<String>, serde_json::Error> {
+    let parsed: ChatResponse = serde_json::from_str(body)?;
+    Ok(parsed.choices.into_iter().next().and_then(|c| c.message.content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_first_choice() {
+        let body = r#"{
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "model": "gpt-4o-mini",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "{\"sql\": \"SELECT 1\"}"}, "finish_reason": "stop"},
+                {"index": 1, "message": {"role": "assistant", "content": "second"}, "finish_reason": "stop"}
+            ],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        }"#;
+        assert_eq!(completion(body).unwrap().as_deref(), Some(r#"{"sql": "SELECT 1"}"#));
+    }
+
+    #[test]
+    fn no_choices_or_no_content_is_no_completion() {
+        assert_eq!(completion(r#"{"choices": []}"#).unwrap(), None);
+        assert_eq!(
+            completion(r#"{"choices": [{"message": {"role": "assistant", "content": null}}]}"#).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_bodies_that_are_not_completions() {
+        assert!(completion(r#"{"error": {"message": "overloaded"}}"#).is_err());
+        assert!(completion("<html>Bad Gateway</html>").is_err());
+    }
+}
@@ -1,14 +1,23 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use tower_http::cors::{Any, CorsLayer};
 
+mod config;
 mod db;
 mod error;
+mod extract;
+mod llm;
 mod models;
 mod routes;
+#[cfg(test)]
+mod test_util;
+mod validate;
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
+    error::set_debug(config::debug_errors());
 
     let pool = db::create_pool().await;
     let readonly_pool = db::create_readonly_pool().await;
@@ -16,14 +25,38 @@ async fn main() {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers([routes::list::TOTAL_COUNT, routes::list::NEXT_CURSOR]);
 
-    let state = routes::AppState { pool, readonly_pool };
+    let sql = config::SqlConfig::from_env();
+    let sql_cursors = routes::sql::CursorStore::new(Duration::from_secs(sql.cursor_ttl_secs));
+    let sql_audit = routes::sql::AuditLog::spawn(pool.clone());
+    let sql_cache =
+        routes::sql::ResultCache::new(Duration::from_secs(sql.cache_ttl_secs), sql.cache_max_bytes);
+    let schema_stats = routes::schema::StatsCache::new(config::SchemaConfig::from_env());
+    let metrics = routes::metrics::Catalog::from_env().expect("Invalid metrics catalog");
+    let llm_config = config::LlmConfig::from_env();
+    let llm = llm::from_config(&llm_config).expect("Invalid LLM configuration");
+
+    let state = routes::AppState {
+        pool,
+        readonly_pool,
+        sql,
+        sql_cursors,
+        sql_audit,
+        sql_cache,
+        schema_stats,
+        metrics: Arc::new(metrics),
+        llm,
+        llm_config,
+    };
     let app = routes::create_router(state).layer(cors);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
     tracing::info!("Listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
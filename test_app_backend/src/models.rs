@@ -1,8 +1,32 @@
 use chrono::{NaiveDate, NaiveDateTime};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::FromRow;
 
+use crate::validate::{Validate, Validator};
+
+/// A column the schema pins to a fixed set of values with a CHECK
+/// constraint, stored as `VARCHAR`. `VALUES` lists them in declaration
+/// order for `/api/enums`.
+macro_rules! value_enum {
+    ($name:ident { $($variant:ident = $value:literal),+ $(,)? }) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+        #[sqlx(type_name = "varchar")]
+        pub enum $name {
+            $(
+                #[serde(rename = $value)]
+                #[sqlx(rename = $value)]
+                $variant,
+            )+
+        }
+
+        impl $name {
+            pub const VALUES: &'static [&'static str] = &[$($value),+];
+        }
+    };
+}
+
 // ── Locations ────────────────────────────────────────────────
 #[derive(Debug, FromRow, Serialize)]
 pub struct Location {
@@ -14,7 +38,7 @@ pub struct Location {
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateLocation {
     pub name: String,
     pub city: String,
@@ -22,14 +46,31 @@ pub struct CreateLocation {
     pub phone: Option<String>,
 }
 
+impl Validate for CreateLocation {
+    fn validate(&self, v: &mut Validator) {
+        v.required("name", &self.name, 100)
+            .required("city", &self.city, 100)
+            .required("address", &self.address, 255)
+            .max_len("phone", self.phone.as_deref(), 20)
+            .phone("phone", self.phone.as_deref());
+    }
+}
+
 // ── Employees ────────────────────────────────────────────────
+value_enum!(EmployeeRole {
+    Manager = "manager",
+    Agent = "agent",
+    Mechanic = "mechanic",
+    Receptionist = "receptionist",
+});
+
 #[derive(Debug, FromRow, Serialize)]
 pub struct Employee {
     pub id: i32,
     pub location_id: i32,
     pub first_name: String,
     pub last_name: String,
-    pub role: String,
+    pub role: EmployeeRole,
     pub salary: Decimal,
     pub hire_date: NaiveDate,
     pub email: Option<String>,
@@ -37,18 +78,30 @@ pub struct Employee {
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateEmployee {
     pub location_id: i32,
     pub first_name: String,
     pub last_name: String,
-    pub role: String,
+    pub role: EmployeeRole,
     pub salary: Decimal,
     pub hire_date: NaiveDate,
     pub email: Option<String>,
     pub phone: Option<String>,
 }
 
+impl Validate for CreateEmployee {
+    fn validate(&self, v: &mut Validator) {
+        v.required("first_name", &self.first_name, 50)
+            .required("last_name", &self.last_name, 50)
+            .money("salary", self.salary)
+            .max_len("email", self.email.as_deref(), 150)
+            .email("email", self.email.as_deref())
+            .max_len("phone", self.phone.as_deref(), 20)
+            .phone("phone", self.phone.as_deref());
+    }
+}
+
 // ── Vehicle Categories ───────────────────────────────────────
 #[derive(Debug, FromRow, Serialize)]
 pub struct VehicleCategory {
@@ -59,7 +112,7 @@ pub struct VehicleCategory {
     pub daily_rate_max: Decimal,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateVehicleCategory {
     pub name: String,
     pub description: Option<String>,
@@ -67,7 +120,27 @@ pub struct CreateVehicleCategory {
     pub daily_rate_max: Decimal,
 }
 
+impl Validate for CreateVehicleCategory {
+    fn validate(&self, v: &mut Validator) {
+        v.required("name", &self.name, 50)
+            .money("daily_rate_min", self.daily_rate_min)
+            .money("daily_rate_max", self.daily_rate_max)
+            .rule(
+                "daily_rate_max",
+                self.daily_rate_max >= self.daily_rate_min,
+                "must not be less than daily_rate_min",
+            );
+    }
+}
+
 // ── Vehicles ─────────────────────────────────────────────────
+value_enum!(VehicleStatus {
+    Available = "available",
+    Rented = "rented",
+    Maintenance = "maintenance",
+    Retired = "retired",
+});
+
 #[derive(Debug, FromRow, Serialize)]
 pub struct Vehicle {
     pub id: i32,
@@ -80,11 +153,11 @@ pub struct Vehicle {
     pub color: Option<String>,
     pub daily_rate: Decimal,
     pub mileage: i32,
-    pub status: String,
+    pub status: VehicleStatus,
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateVehicle {
     pub category_id: i32,
     pub location_id: i32,
@@ -95,7 +168,21 @@ pub struct CreateVehicle {
     pub color: Option<String>,
     pub daily_rate: Decimal,
     pub mileage: Option<i32>,
-    pub status: Option<String>,
+    pub status: Option<VehicleStatus>,
+}
+
+/// Whether `daily_rate` lies within the category's range needs the
+/// category, so it's checked by the vehicles resource instead.
+impl Validate for CreateVehicle {
+    fn validate(&self, v: &mut Validator) {
+        v.required("make", &self.make, 50)
+            .required("model", &self.model, 50)
+            .range("year", self.year, 2015, 2025)
+            .required("license_plate", &self.license_plate, 20)
+            .max_len("color", self.color.as_deref(), 30)
+            .money("daily_rate", self.daily_rate)
+            .at_least("mileage", self.mileage, 0);
+    }
 }
 
 // ── Clients ──────────────────────────────────────────────────
@@ -112,7 +199,7 @@ pub struct Client {
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateClient {
     pub first_name: String,
     pub last_name: String,
@@ -123,7 +210,30 @@ pub struct CreateClient {
     pub registration_date: Option<NaiveDate>,
 }
 
+impl Validate for CreateClient {
+    fn validate(&self, v: &mut Validator) {
+        v.required("first_name", &self.first_name, 50)
+            .required("last_name", &self.last_name, 50)
+            .required("email", &self.email, 150)
+            .email("email", self.email.as_str())
+            .max_len("phone", self.phone.as_deref(), 20)
+            .phone("phone", self.phone.as_deref())
+            .required("drivers_license", &self.drivers_license, 30);
+        if let Some(registered) = self.registration_date {
+            v.not_before("registration_date", registered, "date_of_birth", self.date_of_birth);
+        }
+    }
+}
+
 // ── Reservations ─────────────────────────────────────────────
+value_enum!(ReservationStatus {
+    Confirmed = "confirmed",
+    Active = "active",
+    Completed = "completed",
+    Cancelled = "cancelled",
+    NoShow = "no_show",
+});
+
 #[derive(Debug, FromRow, Serialize)]
 pub struct Reservation {
     pub id: i32,
@@ -133,12 +243,12 @@ pub struct Reservation {
     pub return_location: i32,
     pub pickup_date: NaiveDate,
     pub return_date: NaiveDate,
-    pub status: String,
+    pub status: ReservationStatus,
     pub total_cost: Option<Decimal>,
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateReservation {
     pub client_id: i32,
     pub vehicle_id: i32,
@@ -146,37 +256,75 @@ pub struct CreateReservation {
     pub return_location: i32,
     pub pickup_date: NaiveDate,
     pub return_date: NaiveDate,
-    pub status: Option<String>,
+    pub status: Option<ReservationStatus>,
     pub total_cost: Option<Decimal>,
 }
 
+impl Validate for CreateReservation {
+    fn validate(&self, v: &mut Validator) {
+        v.not_before("return_date", self.return_date, "pickup_date", self.pickup_date)
+            .money("total_cost", self.total_cost);
+    }
+}
+
 // ── Payments ─────────────────────────────────────────────────
+value_enum!(PaymentMethod {
+    CreditCard = "credit_card",
+    DebitCard = "debit_card",
+    Cash = "cash",
+});
+
+value_enum!(PaymentStatus {
+    Completed = "completed",
+    Pending = "pending",
+    Refunded = "refunded",
+    Failed = "failed",
+});
+
 #[derive(Debug, FromRow, Serialize)]
 pub struct Payment {
     pub id: i32,
     pub reservation_id: i32,
     pub amount: Decimal,
-    pub payment_method: String,
+    pub payment_method: PaymentMethod,
     pub payment_date: NaiveDate,
-    pub status: String,
+    pub status: PaymentStatus,
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreatePayment {
     pub reservation_id: i32,
     pub amount: Decimal,
-    pub payment_method: String,
+    pub payment_method: PaymentMethod,
     pub payment_date: NaiveDate,
-    pub status: Option<String>,
+    pub status: Option<PaymentStatus>,
+}
+
+impl Validate for CreatePayment {
+    fn validate(&self, v: &mut Validator) {
+        v.money("amount", self.amount);
+    }
 }
 
 // ── Maintenance Records ──────────────────────────────────────
+value_enum!(MaintenanceType {
+    OilChange = "oil_change",
+    TireRotation = "tire_rotation",
+    BrakeService = "brake_service",
+    GeneralInspection = "general_inspection",
+    EngineRepair = "engine_repair",
+    BodyRepair = "body_repair",
+    Transmission = "transmission",
+    AcService = "ac_service",
+    BatteryReplacement = "battery_replacement",
+});
+
 #[derive(Debug, FromRow, Serialize)]
 pub struct MaintenanceRecord {
     pub id: i32,
     pub vehicle_id: i32,
-    pub maintenance_type: String,
+    pub maintenance_type: MaintenanceType,
     pub description: Option<String>,
     pub cost: Decimal,
     pub maintenance_date: NaiveDate,
@@ -185,10 +333,10 @@ pub struct MaintenanceRecord {
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateMaintenanceRecord {
     pub vehicle_id: i32,
-    pub maintenance_type: String,
+    pub maintenance_type: MaintenanceType,
     pub description: Option<String>,
     pub cost: Decimal,
     pub maintenance_date: NaiveDate,
@@ -196,6 +344,13 @@ pub struct CreateMaintenanceRecord {
     pub completed: Option<bool>,
 }
 
+impl Validate for CreateMaintenanceRecord {
+    fn validate(&self, v: &mut Validator) {
+        v.money("cost", self.cost)
+            .at_least("mileage_at_service", self.mileage_at_service, 0);
+    }
+}
+
 // ── Reviews ──────────────────────────────────────────────────
 #[derive(Debug, FromRow, Serialize)]
 pub struct Review {
@@ -207,7 +362,7 @@ pub struct Review {
     pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateReview {
     pub reservation_id: i32,
     pub rating: i32,
@@ -215,6 +370,12 @@ pub struct CreateReview {
     pub review_date: NaiveDate,
 }
 
+impl Validate for CreateReview {
+    fn validate(&self, v: &mut Validator) {
+        v.range("rating", self.rating, 1, 5);
+    }
+}
+
 // ── Dashboard Aggregates ─────────────────────────────────────
 #[derive(Debug, Serialize)]
 pub struct DashboardSummary {
@@ -257,3 +418,36 @@ pub struct ClientStat {
     pub reservation_count: Option<i64>,
     pub avg_rating: Option<f64>,
 }
+
+// ── Saved Queries ────────────────────────────────────────────
+/// A named `$n` placeholder of a saved query, in placeholder order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQueryParam {
+    pub name: String,
+    pub description: Option<String>,
+    /// Type Postgres infers for the placeholder; filled in on save.
+    #[serde(default)]
+    pub pg_type: Option<String>,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct SavedQuery {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub query: String,
+    pub params: Json<Vec<SavedQueryParam>>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_duration_ms: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSavedQuery {
+    pub name: String,
+    pub description: Option<String>,
+    pub query: String,
+    #[serde(default)]
+    pub params: Vec<SavedQueryParam>,
+}
@@ -0,0 +1,128 @@
+//! Column descriptions and typed values shared by the resource handlers
+//! and list filters.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use sqlx::{Postgres, QueryBuilder};
+
+/// How values for a column are parsed before being bound.
+#[derive(Clone, Copy)]
+pub enum FieldType {
+    Int,
+    Decimal,
+    Text,
+    /// Text limited to the listed values, e.g. a status.
+    Choice(&'static [&'static str]),
+    Date,
+    Timestamp,
+    Bool,
+}
+
+/// A column of a resource's table.
+pub struct Column {
+    pub name: &'static str,
+    pub ty: FieldType,
+    pub access: Access,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Access {
+    /// Set by the database, e.g. `id` and `created_at`.
+    ReadOnly,
+    /// Written by clients and never NULL.
+    Writable,
+    /// Written by clients and may be set to NULL.
+    Nullable,
+}
+
+impl Column {
+    pub const fn read_only(name: &'static str, ty: FieldType) -> Self {
+        Self { name, ty, access: Access::ReadOnly }
+    }
+
+    pub const fn writable(name: &'static str, ty: FieldType) -> Self {
+        Self { name, ty, access: Access::Writable }
+    }
+
+    pub const fn nullable(name: &'static str, ty: FieldType) -> Self {
+        Self { name, ty, access: Access::Nullable }
+    }
+}
+
+/// A value parsed to its column's type, ready to bind.
+#[derive(Clone)]
+pub enum Bound {
+    Int(i32),
+    Decimal(Decimal),
+    Text(String),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    Bool(bool),
+    Null(FieldType),
+}
+
+impl Bound {
+    /// Parses a query-string value.
+    pub fn parse(ty: FieldType, value: &str) -> Result<Self, &'static str> {
+        let value = value.trim();
+        match ty {
+            FieldType::Int => value.parse().map(Bound::Int).map_err(|_| "expected an integer"),
+            FieldType::Decimal => value.parse().map(Bound::Decimal).map_err(|_| "expected a number"),
+            FieldType::Text => Ok(Bound::Text(value.to_string())),
+            FieldType::Choice(values) if values.contains(&value) => Ok(Bound::Text(value.to_string())),
+            FieldType::Choice(_) => Err("not one of the allowed values (see /api/enums)"),
+            FieldType::Date => value.parse().map(Bound::Date).map_err(|_| "expected a date (YYYY-MM-DD)"),
+            // A bare date means midnight, so `created_at[gte]=2024-01-01` works.
+            FieldType::Timestamp => value
+                .parse()
+                .or_else(|_| value.parse::<NaiveDate>().map(|d| d.and_time(Default::default())))
+                .map(Bound::Timestamp)
+                .map_err(|_| "expected a timestamp (YYYY-MM-DDTHH:MM:SS) or a date"),
+            FieldType::Bool => value.parse().map(Bound::Bool).map_err(|_| "expected true or false"),
+        }
+    }
+
+    /// Reads a JSON request body value. Decimals may be numbers or strings,
+    /// as they are serialized as strings; dates must be strings.
+    pub fn from_json(ty: FieldType, value: &Value) -> Result<Self, &'static str> {
+        match (ty, value) {
+            (_, Value::Null) => Ok(Bound::Null(ty)),
+            (FieldType::Int, Value::Number(n)) => n
+                .as_i64()
+                .and_then(|n| i32::try_from(n).ok())
+                .map(Bound::Int)
+                .ok_or("expected an integer"),
+            (FieldType::Int, _) => Err("expected an integer"),
+            (FieldType::Decimal, Value::Number(n)) => Bound::parse(ty, &n.to_string()),
+            (FieldType::Decimal, Value::String(s)) => Bound::parse(ty, s),
+            (FieldType::Decimal, _) => Err("expected a number"),
+            (FieldType::Text, Value::String(s)) => Ok(Bound::Text(s.clone())),
+            (FieldType::Text, _) => Err("expected a string"),
+            (FieldType::Choice(_), Value::String(s)) => Bound::parse(ty, s),
+            (FieldType::Choice(_), _) => Err("expected a string"),
+            (FieldType::Date | FieldType::Timestamp, Value::String(s)) => Bound::parse(ty, s),
+            (FieldType::Date, _) => Err("expected a date (YYYY-MM-DD)"),
+            (FieldType::Timestamp, _) => Err("expected a timestamp (YYYY-MM-DDTHH:MM:SS)"),
+            (FieldType::Bool, Value::Bool(b)) => Ok(Bound::Bool(*b)),
+            (FieldType::Bool, _) => Err("expected true or false"),
+        }
+    }
+
+    pub fn push(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        match self.clone() {
+            Bound::Int(v) => qb.push_bind(v),
+            Bound::Decimal(v) => qb.push_bind(v),
+            Bound::Text(v) => qb.push_bind(v),
+            Bound::Date(v) => qb.push_bind(v),
+            Bound::Timestamp(v) => qb.push_bind(v),
+            Bound::Bool(v) => qb.push_bind(v),
+            Bound::Null(FieldType::Int) => qb.push_bind(None::<i32>),
+            Bound::Null(FieldType::Decimal) => qb.push_bind(None::<Decimal>),
+            Bound::Null(FieldType::Text | FieldType::Choice(_)) => qb.push_bind(None::<String>),
+            Bound::Null(FieldType::Date) => qb.push_bind(None::<NaiveDate>),
+            Bound::Null(FieldType::Timestamp) => qb.push_bind(None::<NaiveDateTime>),
+            Bound::Null(FieldType::Bool) => qb.push_bind(None::<bool>),
+        };
+    }
+}
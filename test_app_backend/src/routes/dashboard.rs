@@ -1,119 +1,127 @@
+//! Dashboard aggregates, computed from the metric catalog so they use the
+//! same definitions as `/api/metrics/query`.
+
 use axum::extract::State;
 use axum::Json;
-use sqlx::PgPool;
+use sqlx::Row;
 
+use super::metrics::{self, MetricQuery, TimeGrain};
+use super::AppState;
 use crate::error::AppError;
 use crate::models::{ClientStat, DashboardSummary, RevenueByMonth, TopVehicle};
 
-pub async fn summary(State(pool): State<PgPool>) -> Result<Json<DashboardSummary>, AppError> {
-    let row = sqlx::query_as::<_, CountRow>(
-        "SELECT \
-            (SELECT COUNT(*) FROM locations) as val"
-    ).fetch_one(&pool).await?;
-    let total_locations = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM employees").fetch_one(&pool).await?;
-    let total_employees = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM vehicles").fetch_one(&pool).await?;
-    let total_vehicles = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM clients").fetch_one(&pool).await?;
-    let total_clients = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM reservations").fetch_one(&pool).await?;
-    let total_reservations = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM reservations WHERE status IN ('active','confirmed')").fetch_one(&pool).await?;
-    let active_reservations = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM payments").fetch_one(&pool).await?;
-    let total_payments = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM maintenance_records").fetch_one(&pool).await?;
-    let total_maintenance = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, CountRow>("SELECT COUNT(*) as val FROM reviews").fetch_one(&pool).await?;
-    let total_reviews = row.val.unwrap_or(0);
-
-    let row = sqlx::query_as::<_, DecimalRow>("SELECT COALESCE(SUM(amount), 0) as val FROM payments WHERE status = 'completed'").fetch_one(&pool).await?;
-    let total_revenue = row.val.unwrap_or_default();
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
 
-    let row = sqlx::query_as::<_, FloatRow>("SELECT COALESCE(AVG(rating)::float8, 0) as val FROM reviews").fetch_one(&pool).await?;
-    let avg_rating = row.val.unwrap_or(0.0);
+pub async fn summary(State(state): State<AppState>) -> Result<Json<DashboardSummary>, AppError> {
+    let q = MetricQuery {
+        metrics: names(&[
+            "locations",
+            "employees",
+            "vehicles",
+            "clients",
+            "reservations",
+            "active_reservations",
+            "payments",
+            "maintenance_records",
+            "reviews",
+            "revenue",
+            "avg_rating",
+        ]),
+        ..Default::default()
+    };
+    let rows = metrics::fetch(&state, &q).await?;
+    let row = rows.first().ok_or(sqlx::Error::RowNotFound)?;
+
+    let count = |name: &str| -> Result<i64, sqlx::Error> {
+        Ok(row.try_get::<Option<i64>, _>(name)?.unwrap_or(0))
+    };
 
     Ok(Json(DashboardSummary {
-        total_locations,
-        total_employees,
-        total_vehicles,
-        total_clients,
-        total_reservations,
-        active_reservations,
-        total_payments,
-        total_maintenance,
-        total_reviews,
-        total_revenue,
-        avg_rating,
+        total_locations: count("locations")?,
+        total_employees: count("employees")?,
+        total_vehicles: count("vehicles")?,
+        total_clients: count("clients")?,
+        total_reservations: count("reservations")?,
+        active_reservations: count("active_reservations")?,
+        total_payments: count("payments")?,
+        total_maintenance: count("maintenance_records")?,
+        total_reviews: count("reviews")?,
+        total_revenue: row.try_get::<Option<rust_decimal::Decimal>, _>("revenue")?.unwrap_or_default(),
+        avg_rating: row.try_get::<Option<f64>, _>("avg_rating")?.unwrap_or(0.0),
     }))
 }
 
-pub async fn revenue_by_month(State(pool): State<PgPool>) -> Result<Json<Vec<RevenueByMonth>>, AppError> {
-    let rows = sqlx::query_as::<_, RevenueByMonth>(
-        "SELECT DATE_TRUNC('month', r.pickup_date)::DATE as month, \
-         SUM(r.total_cost) as revenue, COUNT(*) as booking_count \
-         FROM reservations r WHERE r.status = 'completed' \
-         GROUP BY DATE_TRUNC('month', r.pickup_date) ORDER BY month",
-    )
-    .fetch_all(&pool)
-    .await?;
+pub async fn revenue_by_month(State(state): State<AppState>) -> Result<Json<Vec<RevenueByMonth>>, AppError> {
+    let q = MetricQuery {
+        metrics: names(&["revenue", "completed_rentals"]),
+        time_grain: Some(TimeGrain::Month),
+        ..Default::default()
+    };
+
+    let rows = metrics::fetch(&state, &q)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(RevenueByMonth {
+                month: row.try_get("period")?,
+                revenue: row.try_get("revenue")?,
+                booking_count: row.try_get("completed_rentals")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()?;
     Ok(Json(rows))
 }
 
-pub async fn top_vehicles(State(pool): State<PgPool>) -> Result<Json<Vec<TopVehicle>>, AppError> {
-    let rows = sqlx::query_as::<_, TopVehicle>(
-        "SELECT v.id as vehicle_id, v.make, v.model, \
-         COUNT(r.id) as rental_count, SUM(r.total_cost) as total_revenue, \
-         AVG(rev.rating)::float8 as avg_rating \
-         FROM vehicles v \
-         JOIN reservations r ON r.vehicle_id = v.id AND r.status = 'completed' \
-         LEFT JOIN reviews rev ON rev.reservation_id = r.id \
-         GROUP BY v.id, v.make, v.model \
-         ORDER BY rental_count DESC LIMIT 20",
-    )
-    .fetch_all(&pool)
-    .await?;
+pub async fn top_vehicles(State(state): State<AppState>) -> Result<Json<Vec<TopVehicle>>, AppError> {
+    let q = MetricQuery {
+        metrics: names(&["completed_rentals", "revenue", "avg_rating"]),
+        dimensions: names(&["vehicle_id", "vehicle_make", "vehicle_model"]),
+        order_by: names(&["-completed_rentals", "-revenue", "vehicle_id"]),
+        limit: Some(20),
+        ..Default::default()
+    };
+
+    let rows = metrics::fetch(&state, &q)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(TopVehicle {
+                vehicle_id: row.try_get("vehicle_id")?,
+                make: row.try_get("vehicle_make")?,
+                model: row.try_get("vehicle_model")?,
+                rental_count: row.try_get("completed_rentals")?,
+                total_revenue: row.try_get("revenue")?,
+                avg_rating: row.try_get("avg_rating")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()?;
     Ok(Json(rows))
 }
 
-pub async fn client_stats(State(pool): State<PgPool>) -> Result<Json<Vec<ClientStat>>, AppError> {
-    let rows = sqlx::query_as::<_, ClientStat>(
-        "SELECT c.id as client_id, c.first_name, c.last_name, \
-         SUM(p.amount) as total_spent, COUNT(DISTINCT r.id) as reservation_count, \
-         AVG(rev.rating)::float8 as avg_rating \
-         FROM clients c \
-         JOIN reservations r ON r.client_id = c.id \
-         JOIN payments p ON p.reservation_id = r.id AND p.status = 'completed' \
-         LEFT JOIN reviews rev ON rev.reservation_id = r.id \
-         GROUP BY c.id, c.first_name, c.last_name \
-         ORDER BY total_spent DESC LIMIT 20",
-    )
-    .fetch_all(&pool)
-    .await?;
+pub async fn client_stats(State(state): State<AppState>) -> Result<Json<Vec<ClientStat>>, AppError> {
+    let q = MetricQuery {
+        metrics: names(&["revenue", "reservations", "avg_rating"]),
+        dimensions: names(&["client_id", "client_first_name", "client_last_name"]),
+        order_by: names(&["-revenue", "client_id"]),
+        limit: Some(20),
+        ..Default::default()
+    };
+
+    let rows = metrics::fetch(&state, &q)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(ClientStat {
+                client_id: row.try_get("client_id")?,
+                first_name: row.try_get("client_first_name")?,
+                last_name: row.try_get("client_last_name")?,
+                total_spent: row.try_get("revenue")?,
+                reservation_count: row.try_get("reservations")?,
+                avg_rating: row.try_get("avg_rating")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()?;
     Ok(Json(rows))
 }
-
-// Helper row types for scalar queries
-#[derive(sqlx::FromRow)]
-struct CountRow {
-    val: Option<i64>,
-}
-
-#[derive(sqlx::FromRow)]
-struct DecimalRow {
-    val: Option<rust_decimal::Decimal>,
-}
-
-#[derive(sqlx::FromRow)]
-struct FloatRow {
-    val: Option<f64>,
-}
@@ -0,0 +1,21 @@
+//! The fixed value sets of enum columns, so clients can offer a choice
+//! instead of free text.
+
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::models::{EmployeeRole, MaintenanceType, PaymentMethod, PaymentStatus, ReservationStatus, VehicleStatus};
+
+/// `{resource: {field: [value, ...]}}`, keyed like `/api/{resource}`.
+pub async fn list() -> Json<Value> {
+    Json(json!({
+        "employees": { "role": EmployeeRole::VALUES },
+        "vehicles": { "status": VehicleStatus::VALUES },
+        "reservations": { "status": ReservationStatus::VALUES },
+        "payments": {
+            "payment_method": PaymentMethod::VALUES,
+            "status": PaymentStatus::VALUES,
+        },
+        "maintenance": { "maintenance_type": MaintenanceType::VALUES },
+    }))
+}
@@ -0,0 +1,392 @@
+//! Paging, sorting and filtering shared by the CRUD `list` endpoints.
+//!
+//! ```text
+//! GET /api/vehicles?status=available&year[gte]=2020&sort=-daily_rate,make&limit=25
+//! GET /api/reservations?pickup_date[gte]=2024-01-01&pickup_date[lt]=2024-02-01
+//! GET /api/clients?last_name[contains]=smi
+//! GET /api/vehicles?sort=make&limit=25&after=<X-Next-Cursor of the previous page>
+//! ```
+//!
+//! | parameter            | meaning                                                    |
+//! |----------------------|------------------------------------------------------------|
+//! | `limit`              | page size, default 100, at most 1000                       |
+//! | `offset`             | rows to skip                                               |
+//! | `after`              | keyset cursor from `X-Next-Cursor`; excludes `offset`      |
+//! | `sort`               | comma-separated fields, `-` prefix for descending          |
+//! | `field`              | equals                                                     |
+//! | `field[op]`          | `ne`, `gt`, `gte`, `lt`, `lte`, `in` (comma-separated),    |
+//! |                      | `null` (`true` or `false`), `contains` (case-insensitive   |
+//! |                      | substring, text fields only)                               |
+//!
+//! Rows come back as a plain JSON array. `X-Total-Count` carries how many
+//! rows match the filters, and `X-Next-Cursor` is set when more follow.
+
+use axum::extract::{FromRequestParts, Query};
+use axum::http::header::HeaderValue;
+use axum::http::request::Parts;
+use axum::http::HeaderName;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
+
+use super::column::{Bound, Column, FieldType};
+use crate::error::{AppError, FieldError};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+pub const TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+pub const NEXT_CURSOR: HeaderName = HeaderName::from_static("x-next-cursor");
+
+/// A resource's table and the columns a list request may sort and filter
+/// on. Anything not listed is rejected, so names can go into SQL as is.
+pub struct ListSpec {
+    pub table: &'static str,
+    pub columns: &'static [Column],
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    In,
+    Null,
+    Contains,
+}
+
+impl Op {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "eq" => Op::Eq,
+            "ne" => Op::Ne,
+            "gt" => Op::Gt,
+            "gte" => Op::Gte,
+            "lt" => Op::Lt,
+            "lte" => Op::Lte,
+            "in" => Op::In,
+            "null" => Op::Null,
+            "contains" => Op::Contains,
+            _ => return None,
+        })
+    }
+}
+
+struct Filter {
+    field: String,
+    op: Op,
+    value: String,
+    /// The parameter as written, for error messages.
+    param: String,
+}
+
+/// The query string of a list request, before it is checked against a
+/// resource's `ListSpec`.
+pub struct ListQuery {
+    limit: Option<String>,
+    offset: Option<String>,
+    after: Option<String>,
+    sort: Option<String>,
+    filters: Vec<Filter>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ListQuery {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, AppError> {
+        let Query(pairs) = Query::<Vec<(String, String)>>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::bad_request(e.body_text()))?;
+
+        let mut query = ListQuery {
+            limit: None,
+            offset: None,
+            after: None,
+            sort: None,
+            filters: vec![],
+        };
+        for (key, value) in pairs {
+            match key.as_str() {
+                "limit" => query.limit = Some(value),
+                "offset" => query.offset = Some(value),
+                "after" => query.after = Some(value),
+                "sort" => query.sort = Some(value),
+                _ => {
+                    let (field, op) = match key.strip_suffix(']').and_then(|k| k.split_once('[')) {
+                        Some((field, op)) => {
+                            let op = Op::parse(op).ok_or_else(|| {
+                                invalid(&key, "unknown operator; use eq, ne, gt, gte, lt, lte, in, null or contains")
+                            })?;
+                            (field.to_string(), op)
+                        }
+                        None => (key.clone(), Op::Eq),
+                    };
+                    query.filters.push(Filter { field, op, value, param: key });
+                }
+            }
+        }
+        Ok(query)
+    }
+}
+
+/// A page of rows plus the headers describing where it sits.
+pub struct ListPage<T> {
+    items: Vec<T>,
+    total: i64,
+    next_cursor: Option<String>,
+}
+
+impl<T: Serialize> IntoResponse for ListPage<T> {
+    fn into_response(self) -> Response {
+        let mut response = Json(self.items).into_response();
+        let headers = response.headers_mut();
+        headers.insert(TOTAL_COUNT, HeaderValue::from(self.total));
+        if let Some(cursor) = self.next_cursor.and_then(|c| HeaderValue::from_str(&c).ok()) {
+            headers.insert(NEXT_CURSOR, cursor);
+        }
+        response
+    }
+}
+
+/// A filter checked against the spec.
+enum Condition {
+    Compare(&'static str, &'static str, Bound),
+    In(&'static str, Vec<Bound>),
+    Null(&'static str, bool),
+    /// `ILIKE` pattern, with the value's `%`, `_` and `\` escaped.
+    Contains(&'static str, String),
+}
+
+/// One `ORDER BY` key; `value` is the cursor row's value when paging with
+/// `after`.
+struct SortKey {
+    field: &'static str,
+    ty: FieldType,
+    desc: bool,
+    value: Option<Bound>,
+}
+
+fn invalid(param: &str, message: &str) -> AppError {
+    AppError {
+        fields: vec![FieldError::new(param, message)],
+        ..AppError::bad_request(format!("Invalid list parameter {}: {}", param, message))
+    }
+}
+
+impl ListSpec {
+    fn field(&self, name: &str) -> Result<(&'static str, FieldType), AppError> {
+        self.columns
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| (c.name, c.ty))
+            .ok_or_else(|| invalid(name, "unknown field"))
+    }
+
+    /// Runs `query` against the table and returns the page, checking every
+    /// parameter against the spec first. Rows are always ordered by `id`
+    /// last, so pages are stable and cursors unambiguous.
+    pub async fn fetch<T>(&self, pool: &PgPool, query: &ListQuery) -> Result<ListPage<T>, AppError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Serialize + Send + Unpin,
+    {
+        let limit = match &query.limit {
+            None => DEFAULT_LIMIT,
+            Some(raw) => raw
+                .parse::<i64>()
+                .ok()
+                .filter(|n| (1..=MAX_LIMIT).contains(n))
+                .ok_or_else(|| invalid("limit", &format!("must be between 1 and {}", MAX_LIMIT)))?,
+        };
+        let offset = match &query.offset {
+            None => 0,
+            Some(raw) => raw
+                .parse::<i64>()
+                .ok()
+                .filter(|n| *n >= 0)
+                .ok_or_else(|| invalid("offset", "must be a non-negative integer"))?,
+        };
+        if query.after.is_some() && query.offset.is_some() {
+            return Err(invalid("after", "can't be combined with offset"));
+        }
+
+        let conditions = self.conditions(&query.filters)?;
+        let mut sort = self.sort_keys(query.sort.as_deref())?;
+        if let Some(cursor) = &query.after {
+            let values = decode_cursor(cursor, sort.len()).ok_or_else(|| invalid("after", "not a cursor for this sort"))?;
+            for (key, value) in sort.iter_mut().zip(values) {
+                key.value = value
+                    .map(|v| Bound::parse(key.ty, &v))
+                    .transpose()
+                    .map_err(|_| invalid("after", "not a cursor for this sort"))?;
+            }
+        }
+
+        let mut count = QueryBuilder::new(format!("SELECT COUNT(*) FROM {}", self.table));
+        self.push_where(&mut count, &conditions);
+        let total: i64 = count.build_query_scalar().fetch_one(pool).await?;
+
+        let mut select = QueryBuilder::new(format!("SELECT * FROM {}", self.table));
+        self.push_where(&mut select, &conditions);
+        if query.after.is_some() {
+            push_after(&mut select, &sort);
+        }
+        select.push(" ORDER BY ");
+        for (i, key) in sort.iter().enumerate() {
+            if i > 0 {
+                select.push(", ");
+            }
+            select.push(key.field).push(if key.desc { " DESC NULLS LAST" } else { " ASC NULLS LAST" });
+        }
+        // One extra row tells whether another page follows.
+        select.push(" LIMIT ").push_bind(limit + 1).push(" OFFSET ").push_bind(offset);
+
+        let mut items: Vec<T> = select.build_query_as().fetch_all(pool).await?;
+        let next_cursor = if items.len() as i64 > limit {
+            items.truncate(limit as usize);
+            items.last().map(|last| encode_cursor(last, &sort))
+        } else {
+            None
+        };
+
+        Ok(ListPage { items, total, next_cursor })
+    }
+
+    fn conditions(&self, filters: &[Filter]) -> Result<Vec<Condition>, AppError> {
+        let mut conditions = vec![];
+        for filter in filters {
+            let (field, ty) = self.field(&filter.field).map_err(|_| invalid(&filter.param, "unknown field"))?;
+            let parse = |v: &str| Bound::parse(ty, v).map_err(|e| invalid(&filter.param, e));
+            let compare = |sql| Ok::<_, AppError>(Condition::Compare(field, sql, parse(&filter.value)?));
+            conditions.push(match filter.op {
+                Op::Eq => compare(" = ")?,
+                Op::Ne => compare(" <> ")?,
+                Op::Gt => compare(" > ")?,
+                Op::Gte => compare(" >= ")?,
+                Op::Lt => compare(" < ")?,
+                Op::Lte => compare(" <= ")?,
+                Op::In => Condition::In(field, filter.value.split(',').map(parse).collect::<Result<_, _>>()?),
+                Op::Null => Condition::Null(
+                    field,
+                    filter.value.parse().map_err(|_| invalid(&filter.param, "expected true or false"))?,
+                ),
+                Op::Contains => match ty {
+                    FieldType::Text => {
+                        let escaped = filter.value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                        Condition::Contains(field, format!("%{}%", escaped))
+                    }
+                    _ => return Err(invalid(&filter.param, "contains only works on text fields")),
+                },
+            });
+        }
+        Ok(conditions)
+    }
+
+    /// `sort=make,-daily_rate` as keys, with `id` appended as the tiebreaker.
+    fn sort_keys(&self, sort: Option<&str>) -> Result<Vec<SortKey>, AppError> {
+        let mut keys: Vec<SortKey> = vec![];
+        for part in sort.into_iter().flat_map(|s| s.split(',')).map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, desc) = match part.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (part, false),
+            };
+            let (field, ty) = self.field(name).map_err(|_| invalid("sort", &format!("can't sort by '{}'", name)))?;
+            if !keys.iter().any(|k| k.field == field) {
+                keys.push(SortKey { field, ty, desc, value: None });
+            }
+        }
+        if !keys.iter().any(|k| k.field == "id") {
+            keys.push(SortKey { field: "id", ty: FieldType::Int, desc: false, value: None });
+        }
+        Ok(keys)
+    }
+
+    fn push_where(&self, qb: &mut QueryBuilder<'_, Postgres>, conditions: &[Condition]) {
+        qb.push(" WHERE TRUE");
+        for condition in conditions {
+            match condition {
+                Condition::Compare(field, op, value) => {
+                    qb.push(" AND ").push(field).push(op);
+                    value.push(qb);
+                }
+                Condition::In(field, values) => {
+                    qb.push(" AND ").push(field).push(" IN (");
+                    for (i, value) in values.iter().enumerate() {
+                        if i > 0 {
+                            qb.push(", ");
+                        }
+                        value.push(qb);
+                    }
+                    qb.push(")");
+                }
+                Condition::Null(field, is_null) => {
+                    qb.push(" AND ").push(field).push(if *is_null { " IS NULL" } else { " IS NOT NULL" });
+                }
+                Condition::Contains(field, pattern) => {
+                    qb.push(" AND ").push(field).push(" ILIKE ").push_bind(pattern.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Keeps rows that sort strictly after the cursor row: equal on the first
+/// `i` keys and past it on key `i`, for some `i`. NULLs sort last in both
+/// directions, so nothing non-null follows a NULL.
+fn push_after(qb: &mut QueryBuilder<'_, Postgres>, sort: &[SortKey]) {
+    qb.push(" AND (FALSE");
+    for (i, key) in sort.iter().enumerate() {
+        qb.push(" OR (TRUE");
+        for prev in &sort[..i] {
+            qb.push(" AND ").push(prev.field);
+            match &prev.value {
+                Some(value) => {
+                    qb.push(" = ");
+                    value.push(qb);
+                }
+                None => {
+                    qb.push(" IS NULL");
+                }
+            }
+        }
+        match &key.value {
+            Some(value) => {
+                qb.push(" AND (").push(key.field).push(if key.desc { " < " } else { " > " });
+                value.push(qb);
+                qb.push(" OR ").push(key.field).push(" IS NULL)");
+            }
+            None => {
+                qb.push(" AND FALSE");
+            }
+        }
+        qb.push(")");
+    }
+    qb.push(")");
+}
+
+/// The last row's sort key values as base64url JSON. Values are kept as
+/// the strings they serialize to, which `Bound::parse` reads back.
+fn encode_cursor<T: Serialize>(row: &T, sort: &[SortKey]) -> String {
+    let json = serde_json::to_value(row).unwrap_or_default();
+    let values: Vec<Option<String>> = sort
+        .iter()
+        .map(|key| match &json[key.field] {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        })
+        .collect();
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&values).unwrap_or_default())
+}
+
+fn decode_cursor(cursor: &str, keys: usize) -> Option<Vec<Option<String>>> {
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let values: Vec<Option<String>> = serde_json::from_slice(&bytes).ok()?;
+    (values.len() == keys).then_some(values)
+}
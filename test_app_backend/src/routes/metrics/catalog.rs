@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::Deserialize;
+
+use super::compile::MetricQuery;
+
+/// Used unless `METRICS_CATALOG` points at another file.
+const BUILTIN_CATALOG: &str = include_str!("../../../metrics.toml");
+
+/// Every entity reachable from a metric's entity, mapped to its discovery
+/// order and the entity and join it was reached through (`None` for the
+/// root).
+pub(super) type JoinTree<'a> = HashMap<String, (usize, Option<(String, &'a Join)>)>;
+
+/// Output column added by `time_grain`; no metric or dimension may use it.
+pub const PERIOD: &str = "period";
+
+/// The metric and dimension definitions from `metrics.toml`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Catalog {
+    pub entities: BTreeMap<String, Entity>,
+    pub metrics: BTreeMap<String, Metric>,
+    pub dimensions: BTreeMap<String, Dimension>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entity {
+    /// Table the entity reads; defaults to the entity's name, which is then
+    /// used as the alias.
+    pub table: Option<String>,
+    #[serde(default)]
+    pub joins: Vec<Join>,
+}
+
+/// A many-to-one join from an entity to the entity named `to`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Join {
+    pub to: String,
+    pub on: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Metric {
+    pub description: String,
+    pub entity: String,
+    /// Aggregate expression, e.g. `SUM(payments.amount)`.
+    pub expression: String,
+    /// Condition rows must meet to count towards the metric.
+    pub filter: Option<String>,
+    /// Date or timestamp column that `time_grain` and `from`/`to` apply to.
+    pub time: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dimension {
+    pub description: String,
+    pub entity: String,
+    pub expression: String,
+}
+
+impl Catalog {
+    /// Loads the file named by `METRICS_CATALOG`, or the catalog built into
+    /// the binary.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("METRICS_CATALOG") {
+            Ok(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Could not read metrics catalog {}: {}", path, e))?;
+                Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+            }
+            Err(_) => Self::parse(BUILTIN_CATALOG),
+        }
+    }
+
+    /// Parses and checks a catalog: names are plain identifiers, every
+    /// entity reference resolves, and each metric compiles to a read-only
+    /// query together with every dimension available to it.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let catalog: Catalog = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        for name in catalog.entities.keys() {
+            check_name("entity", name)?;
+        }
+        for name in catalog.metrics.keys().chain(catalog.dimensions.keys()) {
+            check_name("metric or dimension", name)?;
+            if name == PERIOD {
+                return Err(format!("'{}' is reserved for the time grain column", PERIOD));
+            }
+        }
+        if let Some(name) = catalog.metrics.keys().find(|m| catalog.dimensions.contains_key(*m)) {
+            return Err(format!("'{}' is defined as both a metric and a dimension", name));
+        }
+
+        for (name, entity) in &catalog.entities {
+            for join in &entity.joins {
+                if !catalog.entities.contains_key(&join.to) {
+                    return Err(format!("Entity '{}' joins unknown entity '{}'", name, join.to));
+                }
+            }
+        }
+        for (name, metric) in &catalog.metrics {
+            if !catalog.entities.contains_key(&metric.entity) {
+                return Err(format!("Metric '{}' reads unknown entity '{}'", name, metric.entity));
+            }
+        }
+        for (name, dimension) in &catalog.dimensions {
+            if !catalog.entities.contains_key(&dimension.entity) {
+                return Err(format!("Dimension '{}' reads unknown entity '{}'", name, dimension.entity));
+            }
+        }
+
+        for name in catalog.metrics.keys() {
+            let query = MetricQuery {
+                metrics: vec![name.clone()],
+                dimensions: catalog.dimensions_for(name),
+                ..Default::default()
+            };
+            catalog
+                .compile(&query)
+                .map_err(|e| format!("Metric '{}' does not compile: {}", name, e))?;
+        }
+
+        Ok(catalog)
+    }
+
+    /// Names of the dimensions whose entity can be reached from `metric`'s.
+    pub fn dimensions_for(&self, metric: &str) -> Vec<String> {
+        let Some(metric) = self.metrics.get(metric) else {
+            return vec![];
+        };
+        let reachable = self.join_tree(&metric.entity);
+        self.dimensions
+            .iter()
+            .filter(|(_, d)| reachable.contains_key(&d.entity))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Breadth-first walk of the joins starting at `root`, so each entity
+    /// has exactly one, shortest path.
+    pub(super) fn join_tree(&self, root: &str) -> JoinTree<'_> {
+        let mut tree = HashMap::new();
+        tree.insert(root.to_string(), (0, None));
+
+        let mut queue = VecDeque::from([root.to_string()]);
+        while let Some(name) = queue.pop_front() {
+            let Some(entity) = self.entities.get(&name) else {
+                continue;
+            };
+            for join in &entity.joins {
+                if !tree.contains_key(&join.to) {
+                    let order = tree.len();
+                    tree.insert(join.to.clone(), (order, Some((name.clone(), join))));
+                    queue.push_back(join.to.clone());
+                }
+            }
+        }
+
+        tree
+    }
+
+    /// `table` or `table AS alias` for use in a FROM or JOIN clause.
+    pub(super) fn relation(&self, entity: &str) -> String {
+        match self.entities.get(entity).and_then(|e| e.table.as_deref()) {
+            Some(table) if table != entity => format!("{} AS {}", table, entity),
+            _ => entity.to_string(),
+        }
+    }
+}
+
+fn check_name(kind: &str, name: &str) -> Result<(), String> {
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid {} name '{}': use lowercase letters, digits and underscores",
+            kind, name
+        ))
+    }
+}
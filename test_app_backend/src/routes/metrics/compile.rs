@@ -0,0 +1,302 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::catalog::{Catalog, PERIOD};
+use crate::routes::sql::validate_readonly_sql;
+
+/// A request for one or more metrics, grouped by dimensions and optionally
+/// by time period.
+#[derive(Deserialize, Default)]
+pub struct MetricQuery {
+    pub metrics: Vec<String>,
+    #[serde(default)]
+    pub dimensions: Vec<String>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    /// Adds a `period` column: each metric's `time` truncated to this grain.
+    pub time_grain: Option<TimeGrain>,
+    /// Inclusive lower and exclusive upper bound on each metric's `time`.
+    pub from: Option<serde_json::Value>,
+    pub to: Option<serde_json::Value>,
+    /// Output columns to sort by; a leading `-` sorts descending.
+    #[serde(default)]
+    pub order_by: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct Filter {
+    pub dimension: String,
+    pub op: FilterOp,
+    /// Bound as a parameter; an array for `in`.
+    pub value: serde_json::Value,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    In,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeGrain {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl TimeGrain {
+    fn as_str(self) -> &'static str {
+        match self {
+            TimeGrain::Day => "day",
+            TimeGrain::Week => "week",
+            TimeGrain::Month => "month",
+            TimeGrain::Quarter => "quarter",
+            TimeGrain::Year => "year",
+        }
+    }
+}
+
+/// SQL for a `MetricQuery`, with filter values left as `$n` placeholders.
+#[derive(Serialize)]
+pub struct CompiledQuery {
+    pub sql: String,
+    pub params: Vec<serde_json::Value>,
+}
+
+impl Catalog {
+    /// Builds one aggregate query per metric over its entity, joined to the
+    /// entities its dimensions and filters need. Several metrics are
+    /// combined on their dimension values, so each keeps its own filter and
+    /// time column and missing combinations come back as nulls.
+    pub fn compile(&self, q: &MetricQuery) -> Result<CompiledQuery, String> {
+        if q.metrics.is_empty() {
+            return Err("At least one metric is required".to_string());
+        }
+        for (i, name) in q.metrics.iter().enumerate() {
+            if !self.metrics.contains_key(name) {
+                return Err(format!("Unknown metric '{}'", name));
+            }
+            if q.metrics[..i].contains(name) {
+                return Err(format!("Metric '{}' is requested twice", name));
+            }
+        }
+        for (i, name) in q.dimensions.iter().enumerate() {
+            if !self.dimensions.contains_key(name) {
+                return Err(format!("Unknown dimension '{}'", name));
+            }
+            if q.dimensions[..i].contains(name) {
+                return Err(format!("Dimension '{}' is requested twice", name));
+            }
+        }
+
+        let mut params = vec![];
+        let mut bind = |value: &serde_json::Value| {
+            params.push(value.clone());
+            format!("${}", params.len())
+        };
+
+        let mut conditions = vec![];
+        for f in &q.filters {
+            let dimension = self
+                .dimensions
+                .get(&f.dimension)
+                .ok_or_else(|| format!("Unknown filter dimension '{}'", f.dimension))?;
+            let expr = &dimension.expression;
+            let condition = match (f.op, &f.value) {
+                (FilterOp::Eq, serde_json::Value::Null) => format!("({}) IS NULL", expr),
+                (FilterOp::Ne, serde_json::Value::Null) => format!("({}) IS NOT NULL", expr),
+                (_, serde_json::Value::Null) => {
+                    return Err(format!("Filter on '{}' compares with null", f.dimension));
+                }
+                (FilterOp::In, serde_json::Value::Array(values)) if !values.is_empty() => {
+                    let placeholders: Vec<String> = values.iter().map(&mut bind).collect();
+                    format!("({}) IN ({})", expr, placeholders.join(", "))
+                }
+                (FilterOp::In, _) => {
+                    return Err(format!(
+                        "Filter on '{}' with op 'in' needs a non-empty array",
+                        f.dimension
+                    ));
+                }
+                (op, value) => {
+                    let op = match op {
+                        FilterOp::Eq => "=",
+                        FilterOp::Ne => "<>",
+                        FilterOp::Gt => ">",
+                        FilterOp::Gte => ">=",
+                        FilterOp::Lt => "<",
+                        FilterOp::Lte => "<=",
+                        FilterOp::In => unreachable!(),
+                    };
+                    format!("({}) {} {}", expr, op, bind(value))
+                }
+            };
+            conditions.push((f.dimension.as_str(), condition));
+        }
+
+        let from = q.from.as_ref().map(&mut bind);
+        let to = q.to.as_ref().map(&mut bind);
+
+        let mut group_columns: Vec<&str> = q.dimensions.iter().map(String::as_str).collect();
+        if q.time_grain.is_some() {
+            group_columns.push(PERIOD);
+        }
+
+        let mut subqueries = vec![];
+        for name in &q.metrics {
+            let metric = &self.metrics[name];
+            let tree = self.join_tree(&metric.entity);
+
+            let needed: BTreeSet<&str> = q
+                .dimensions
+                .iter()
+                .map(String::as_str)
+                .chain(conditions.iter().map(|(d, _)| *d))
+                .collect();
+            let mut joins = vec![];
+            for dimension in needed {
+                let entity = &self.dimensions[dimension].entity;
+                if !tree.contains_key(entity) {
+                    return Err(format!(
+                        "Dimension '{}' is not available for metric '{}'",
+                        dimension, name
+                    ));
+                }
+                let mut current = entity.as_str();
+                while let Some((order, Some((parent, join)))) = tree.get(current) {
+                    if !joins.iter().any(|(o, _, _)| o == order) {
+                        joins.push((*order, current, *join));
+                    }
+                    current = parent;
+                }
+            }
+            joins.sort_by_key(|(order, _, _)| *order);
+
+            let mut select: Vec<String> = q
+                .dimensions
+                .iter()
+                .map(|d| format!("{} AS {}", self.dimensions[d].expression, d))
+                .collect();
+            let mut filters: Vec<String> = metric.filter.iter().map(|f| format!("({})", f)).collect();
+            filters.extend(conditions.iter().map(|(_, c)| c.clone()));
+
+            if q.time_grain.is_some() || from.is_some() || to.is_some() {
+                let time = metric.time.as_ref().ok_or_else(|| {
+                    format!("Metric '{}' has no time column, so it can't use time_grain, from or to", name)
+                })?;
+                if let Some(grain) = q.time_grain {
+                    select.push(format!("date_trunc('{}', {})::date AS {}", grain.as_str(), time, PERIOD));
+                }
+                if let Some(from) = &from {
+                    filters.push(format!("{} >= {}", time, from));
+                }
+                if let Some(to) = &to {
+                    filters.push(format!("{} < {}", time, to));
+                }
+            }
+            select.push(format!("{} AS {}", metric.expression, name));
+
+            let mut sql = format!("SELECT {} FROM {}", select.join(", "), self.relation(&metric.entity));
+            for (_, entity, join) in &joins {
+                sql.push_str(&format!(" LEFT JOIN {} ON {}", self.relation(entity), join.on));
+            }
+            if !filters.is_empty() {
+                sql.push_str(&format!(" WHERE {}", filters.join(" AND ")));
+            }
+            if !group_columns.is_empty() {
+                let positions: Vec<String> = (1..=group_columns.len()).map(|i| i.to_string()).collect();
+                sql.push_str(&format!(" GROUP BY {}", positions.join(", ")));
+            }
+            subqueries.push(sql);
+        }
+
+        let mut sql = match subqueries.as_slice() {
+            [single] => single.clone(),
+            _ => combine(&q.metrics, &subqueries, &group_columns),
+        };
+
+        let mut order = vec![];
+        for field in &q.order_by {
+            let (name, desc) = match field.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (field.as_str(), false),
+            };
+            if !group_columns.contains(&name) && !q.metrics.iter().any(|m| m == name) {
+                return Err(format!("Can't order by '{}': not a requested metric or dimension", name));
+            }
+            order.push(if desc {
+                format!("{} DESC NULLS LAST", name)
+            } else {
+                name.to_string()
+            });
+        }
+        if order.is_empty() {
+            order = group_columns.iter().map(|c| c.to_string()).collect();
+        }
+        if !order.is_empty() {
+            sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+        }
+        if let Some(limit) = q.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        validate_readonly_sql(&sql)?;
+        Ok(CompiledQuery { sql, params })
+    }
+}
+
+/// Joins per-metric subqueries on their group columns. Every combination
+/// any metric produced is kept; without group columns each subquery is a
+/// single row.
+fn combine(metrics: &[String], subqueries: &[String], group_columns: &[&str]) -> String {
+    let ctes: Vec<String> = metrics
+        .iter()
+        .zip(subqueries)
+        .map(|(m, sql)| format!("m_{} AS ({})", m, sql))
+        .collect();
+    let values: Vec<String> = metrics.iter().map(|m| format!("m_{}.{}", m, m)).collect();
+
+    if group_columns.is_empty() {
+        let relations: Vec<String> = metrics.iter().map(|m| format!("m_{}", m)).collect();
+        return format!(
+            "WITH {} SELECT {} FROM {}",
+            ctes.join(", "),
+            values.join(", "),
+            relations.join(" CROSS JOIN ")
+        );
+    }
+
+    let columns = group_columns.join(", ");
+    let keys: Vec<String> = metrics
+        .iter()
+        .map(|m| format!("SELECT {} FROM m_{}", columns, m))
+        .collect();
+    let key_columns: Vec<String> = group_columns.iter().map(|c| format!("keys.{}", c)).collect();
+
+    let mut sql = format!(
+        "WITH {}, keys AS ({}) SELECT {}, {} FROM keys",
+        ctes.join(", "),
+        keys.join(" UNION "),
+        key_columns.join(", "),
+        values.join(", ")
+    );
+    for m in metrics {
+        let on: Vec<String> = group_columns
+            .iter()
+            .map(|c| format!("m_{m}.{c} IS NOT DISTINCT FROM keys.{c}"))
+            .collect();
+        sql.push_str(&format!(" LEFT JOIN m_{} ON {}", m, on.join(" AND ")));
+    }
+    sql
+}
@@ -0,0 +1,132 @@
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+
+use super::sql::{self, SqlRequest};
+use super::AppState;
+use crate::error::{AppError, ErrorCode};
+use crate::extract::AppJson;
+
+mod catalog;
+mod compile;
+
+pub use catalog::Catalog;
+pub use compile::{MetricQuery, TimeGrain};
+
+#[derive(Serialize)]
+struct MetricInfo<'a> {
+    name: &'a str,
+    description: &'a str,
+    entity: &'a str,
+    expression: &'a str,
+    filter: Option<&'a str>,
+    time: Option<&'a str>,
+    dimensions: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct DimensionInfo<'a> {
+    name: &'a str,
+    description: &'a str,
+    entity: &'a str,
+    expression: &'a str,
+}
+
+/// The catalog, with the dimensions each metric can be grouped by.
+pub async fn list(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let catalog = &state.metrics;
+
+    let metrics: Vec<MetricInfo> = catalog
+        .metrics
+        .iter()
+        .map(|(name, m)| MetricInfo {
+            name,
+            description: &m.description,
+            entity: &m.entity,
+            expression: &m.expression,
+            filter: m.filter.as_deref(),
+            time: m.time.as_deref(),
+            dimensions: catalog.dimensions_for(name),
+        })
+        .collect();
+    let dimensions: Vec<DimensionInfo> = catalog
+        .dimensions
+        .iter()
+        .map(|(name, d)| DimensionInfo {
+            name,
+            description: &d.description,
+            entity: &d.entity,
+            expression: &d.expression,
+        })
+        .collect();
+
+    Json(serde_json::json!({ "metrics": metrics, "dimensions": dimensions }))
+}
+
+#[derive(Deserialize)]
+pub struct MetricRequest {
+    #[serde(flatten)]
+    query: MetricQuery,
+    /// Return the compiled SQL and params instead of running them.
+    #[serde(default)]
+    dry_run: bool,
+    max_rows: Option<usize>,
+    timeout_ms: Option<u64>,
+}
+
+/// Compiles a metric request and runs it through `/api/sql`'s read-only
+/// path, so validation, limits, the cost guard, caching and auditing apply.
+pub async fn query(
+    State(state): State<AppState>,
+    peer: ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    AppJson(b): AppJson<MetricRequest>,
+) -> Result<Response, AppError> {
+    let compiled = state
+        .metrics
+        .compile(&b.query)
+        .map_err(AppError::bad_request)?;
+
+    if b.dry_run {
+        return Ok(Json(compiled).into_response());
+    }
+
+    let req = SqlRequest {
+        query: compiled.sql,
+        params: compiled.params,
+        max_rows: b.max_rows,
+        timeout_ms: b.timeout_ms,
+        ..Default::default()
+    };
+    Ok(sql::execute(State(state), peer, headers, AppJson(req)).await)
+}
+
+/// Runs a metric query built in code on the read-only pool, for handlers
+/// that map the rows into their own response types. It gets the same
+/// statement timeout as an `/api/sql` request that doesn't ask for one.
+pub async fn fetch(state: &AppState, q: &MetricQuery) -> Result<Vec<PgRow>, AppError> {
+    let compiled = state
+        .metrics
+        .compile(q)
+        .map_err(AppError::internal)?;
+
+    let params = match sql::resolve_params(&state.readonly_pool, &compiled.sql, &compiled.params).await {
+        Ok(params) => params,
+        Err(sql::ParamError::Query(e)) => return Err(e.into()),
+        Err(_) => {
+            return Err(AppError::new(ErrorCode::InvalidParams, "Invalid metric filter value"))
+        }
+    };
+
+    let args = sql::arguments(&params)?;
+    let (mut tx, cancel) = sql::begin_with_timeout(&state.readonly_pool, state.sql.default_timeout_ms).await?;
+    let rows = sqlx::query_with(&compiled.sql, args).fetch_all(&mut *tx).await;
+    cancel.disarm();
+    tx.rollback().await?;
+    Ok(rows?)
+}
@@ -1,12 +1,31 @@
-use axum::extract::FromRef;
+use std::sync::Arc;
+
+use axum::extract::{FromRef, Request, State};
+use axum::http::Method;
+use axum::middleware::{self, Next};
+use axum::response::Response;
 use axum::routing::{get, post};
 use axum::Router;
 use sqlx::PgPool;
 
+use crate::config::{LlmConfig, SqlConfig};
+use crate::llm::LlmProvider;
+use crate::models::{
+    Client, Employee, Location, MaintenanceRecord, Payment, Reservation, Review, Vehicle, VehicleCategory,
+};
+
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub readonly_pool: PgPool,
+    pub sql: SqlConfig,
+    pub sql_cursors: sql::CursorStore,
+    pub sql_audit: sql::AuditLog,
+    pub sql_cache: sql::ResultCache,
+    pub schema_stats: schema::StatsCache,
+    pub metrics: Arc<metrics::Catalog>,
+    pub llm: Arc<dyn LlmProvider>,
+    pub llm_config: LlmConfig,
 }
 
 // Allows existing handlers with State(pool): State<PgPool> to keep working
@@ -17,42 +36,89 @@ impl FromRef<AppState> for PgPool {
     }
 }
 
-mod categories;
-mod clients;
+mod column;
 mod dashboard;
-mod employees;
-mod locations;
-mod maintenance;
-mod payments;
-mod reservations;
-mod reviews;
-mod sql;
-mod vehicles;
+mod enums;
+pub mod list;
+pub mod metrics;
+mod nl2sql;
+mod resource;
+mod saved_queries;
+pub mod schema;
+pub mod sql;
+
+/// Tables a write through `/api/{resource}` can change, including rows
+/// removed by `ON DELETE CASCADE`.
+fn written_tables(resource: &str) -> &'static [&'static str] {
+    match resource {
+        "locations" => &[
+            "locations",
+            "employees",
+            "vehicles",
+            "reservations",
+            "payments",
+            "reviews",
+            "maintenance_records",
+        ],
+        "employees" => &["employees"],
+        "categories" => &["vehicle_categories"],
+        "vehicles" => &["vehicles", "reservations", "payments", "reviews", "maintenance_records"],
+        "clients" => &["clients", "reservations", "payments", "reviews"],
+        "reservations" => &["reservations", "payments", "reviews"],
+        "payments" => &["payments"],
+        "maintenance" => &["maintenance_records"],
+        "reviews" => &["reviews"],
+        "saved-queries" => &["saved_queries"],
+        _ => &[],
+    }
+}
+
+/// Drops cached `/api/sql` results that read a table once a write request
+/// for it is done. Invalidation also runs if the handler is dropped midway,
+/// since the write may have committed anyway.
+async fn invalidate_sql_cache(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    struct InvalidateOnDrop(sql::ResultCache, &'static [&'static str]);
+
+    impl Drop for InvalidateOnDrop {
+        fn drop(&mut self) {
+            self.0.invalidate(self.1);
+        }
+    }
+
+    let writes = matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    let resource = req.uri().path().trim_start_matches("/api/").split('/').next().unwrap_or("");
+    let tables = written_tables(resource);
+
+    let _invalidate = (writes && !tables.is_empty()).then(|| InvalidateOnDrop(state.sql_cache.clone(), tables));
+    next.run(req).await
+}
 
 pub fn create_router(state: AppState) -> Router {
     Router::new()
-        .route("/api/locations", get(locations::list).post(locations::create))
-        .route("/api/locations/{id}", get(locations::get_one).put(locations::update).delete(locations::delete))
-        .route("/api/employees", get(employees::list).post(employees::create))
-        .route("/api/employees/{id}", get(employees::get_one).put(employees::update).delete(employees::delete))
-        .route("/api/categories", get(categories::list).post(categories::create))
-        .route("/api/categories/{id}", get(categories::get_one).put(categories::update).delete(categories::delete))
-        .route("/api/vehicles", get(vehicles::list).post(vehicles::create))
-        .route("/api/vehicles/{id}", get(vehicles::get_one).put(vehicles::update).delete(vehicles::delete))
-        .route("/api/clients", get(clients::list).post(clients::create))
-        .route("/api/clients/{id}", get(clients::get_one).put(clients::update).delete(clients::delete))
-        .route("/api/reservations", get(reservations::list).post(reservations::create))
-        .route("/api/reservations/{id}", get(reservations::get_one).put(reservations::update).delete(reservations::delete))
-        .route("/api/payments", get(payments::list).post(payments::create))
-        .route("/api/payments/{id}", get(payments::get_one).put(payments::update).delete(payments::delete))
-        .route("/api/maintenance", get(maintenance::list).post(maintenance::create))
-        .route("/api/maintenance/{id}", get(maintenance::get_one).put(maintenance::update).delete(maintenance::delete))
-        .route("/api/reviews", get(reviews::list).post(reviews::create))
-        .route("/api/reviews/{id}", get(reviews::get_one).put(reviews::update).delete(reviews::delete))
+        .merge(resource::routes::<Location>())
+        .merge(resource::routes::<Employee>())
+        .merge(resource::routes::<VehicleCategory>())
+        .merge(resource::routes::<Vehicle>())
+        .merge(resource::routes::<Client>())
+        .merge(resource::routes::<Reservation>())
+        .merge(resource::routes::<Payment>())
+        .merge(resource::routes::<MaintenanceRecord>())
+        .merge(resource::routes::<Review>())
+        .route("/api/enums", get(enums::list))
         .route("/api/dashboard/summary", get(dashboard::summary))
         .route("/api/dashboard/revenue-by-month", get(dashboard::revenue_by_month))
         .route("/api/dashboard/top-vehicles", get(dashboard::top_vehicles))
         .route("/api/dashboard/client-stats", get(dashboard::client_stats))
+        .route("/api/metrics", get(metrics::list))
+        .route("/api/metrics/query", post(metrics::query))
         .route("/api/sql", post(sql::execute))
+        .route("/api/sql/history", get(sql::history))
+        .route("/api/nl2sql", post(nl2sql::generate))
+        .route("/api/schema", get(schema::get_schema))
+        .route("/api/schema/stats", get(schema::get_stats))
+        .route("/api/saved-queries", get(saved_queries::list).post(saved_queries::create))
+        .route("/api/saved-queries/{name}", get(saved_queries::get_one).put(saved_queries::update).delete(saved_queries::delete))
+        .route("/api/saved-queries/{name}/run", post(saved_queries::run))
+        .layer(middleware::from_fn_with_state(state.clone(), invalidate_sql_cache))
         .with_state(state)
 }
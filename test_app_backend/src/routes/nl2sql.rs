@@ -0,0 +1,474 @@
+use std::net::SocketAddr;
+use std::ops::ControlFlow;
+
+use axum::body::to_bytes;
+use axum::extract::{ConnectInfo, State};
+use axum::http::header::ACCEPT;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::visit_relations;
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::parser::Parser;
+
+use super::schema::{introspect, render_prompt, render_prompt_for, Schema};
+use super::sql::{self, placeholder_types, validate_readonly_sql, SqlRequest};
+use super::AppState;
+use crate::error::{AppError, ErrorCode, FieldError};
+use crate::extract::AppJson;
+use crate::llm::Message;
+
+fn system_prompt(schema: &str) -> String {
+    format!(
+        "You translate questions about a PostgreSQL database into one read-only SQL query.\n\
+         \n\
+         {schema}\n\
+         Rules:\n\
+         - Use only the tables and columns listed above.\n\
+         - Write a single SELECT (or WITH ... SELECT) statement; never modify data.\n\
+         - When filtering a column with listed IN(...) values, use those values verbatim.\n\
+         - Limit results to 100 rows unless the question asks for more.\n\
+         \n\
+         Respond with only a JSON object, no markdown: \
+         {{\"sql\": \"SELECT ...\", \"explanation\": \"one sentence\"}}. \
+         If the question can't be answered from this database, respond \
+         {{\"sql\": null, \"explanation\": \"why not\"}}."
+    )
+}
+
+#[derive(Deserialize)]
+pub struct Nl2SqlRequest {
+    question: String,
+    /// Also run the generated query through `/api/sql`. Without it the
+    /// query is only prepared, which still catches unknown columns and type
+    /// errors.
+    #[serde(default)]
+    execute: bool,
+    /// How many times a failing query may be sent back for repair; capped
+    /// by `LLM_MAX_REPAIRS`, which is also the default.
+    max_repairs: Option<u32>,
+    max_rows: Option<usize>,
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct Nl2SqlResponse {
+    question: String,
+    /// The last query generated; `None` when the model found the question
+    /// unanswerable from the schema.
+    sql: Option<String>,
+    explanation: Option<String>,
+    provider: String,
+    /// Every query the model produced, in order, with why it failed.
+    attempts: Vec<Attempt>,
+    /// Whether a repair round fixed a failing query; `None` when none ran.
+    repair_succeeded: Option<bool>,
+    /// The `/api/sql` response body, when `execute` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct Attempt {
+    sql: Option<String>,
+    explanation: Option<String>,
+    error: Option<String>,
+    code: Option<ErrorCode>,
+}
+
+/// Why a generated query can't be returned as-is.
+struct Rejection {
+    status: StatusCode,
+    code: ErrorCode,
+    error: String,
+    result: Option<serde_json::Value>,
+}
+
+impl Rejection {
+    /// Reads an `/api/sql`-style error body. A body without a known `code`
+    /// is taken for a server error, so it is never sent for repair.
+    fn from_error(status: StatusCode, body: &serde_json::Value) -> Self {
+        Self {
+            status,
+            code: serde_json::from_value(body["code"].clone()).unwrap_or(ErrorCode::InternalError),
+            error: body["error"].as_str().unwrap_or_default().to_string(),
+            result: None,
+        }
+    }
+
+    /// Errors the model can plausibly fix by rewriting the query. Timeouts,
+    /// the cost guard and failures of the server itself are left alone.
+    fn repairable(&self) -> bool {
+        matches!(self.code, ErrorCode::QueryFailed | ErrorCode::ForbiddenStatement)
+    }
+}
+
+/// Builds a prompt from the live schema of the read-only role, asks the
+/// configured LLM for a query and checks it with the read-only validator
+/// before returning or running it. A query that fails is sent back with the
+/// error and the schema of the tables it touches, up to `max_repairs` times.
+pub async fn generate(
+    State(state): State<AppState>,
+    peer: ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    AppJson(b): AppJson<Nl2SqlRequest>,
+) -> Result<Response, AppError> {
+    let question = b.question.trim();
+    if question.is_empty() {
+        return Err(AppError::validation(vec![FieldError::new("question", "must not be empty")]));
+    }
+
+    let max_repairs = b
+        .max_repairs
+        .map_or(state.llm_config.max_repairs, |n| n.min(state.llm_config.max_repairs));
+
+    let schema = introspect(&state.readonly_pool).await?;
+    let mut conversation = vec![
+        Message::system(system_prompt(&render_prompt(&schema))),
+        Message::user(question),
+    ];
+
+    let mut body = Nl2SqlResponse {
+        question: question.to_string(),
+        sql: None,
+        explanation: None,
+        provider: state.llm.name(),
+        attempts: vec![],
+        repair_succeeded: None,
+        result: None,
+    };
+    let mut last_rejection: Option<Rejection> = None;
+
+    loop {
+        let reply = match state.llm.complete(&conversation).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                tracing::warn!("LLM request from /api/nl2sql failed: {}", e);
+                return Ok(failure(
+                    ErrorCode::LlmFailed.status(),
+                    ErrorCode::LlmFailed,
+                    format!("LLM request failed: {}", e),
+                    &body,
+                ));
+            }
+        };
+
+        let (sql, explanation) = parse_reply(&reply);
+        body.sql = sql.clone();
+        body.explanation = explanation.clone();
+
+        let Some(sql) = sql else {
+            body.attempts.push(Attempt { sql: None, explanation, error: None, code: None });
+            // Giving up during a repair still leaves the earlier failure.
+            return Ok(match last_rejection {
+                Some(rejection) => {
+                    body.repair_succeeded = Some(false);
+                    body.result = rejection.result;
+                    failure(rejection.status, rejection.code, rejection.error, &body)
+                }
+                None => Json(body).into_response(),
+            });
+        };
+
+        match check(&state, &peer, &headers, &sql, &b).await {
+            Ok(result) => {
+                body.attempts.push(Attempt { sql: Some(sql), explanation, error: None, code: None });
+                body.repair_succeeded = last_rejection.is_some().then_some(true);
+                body.result = result;
+                return Ok(Json(body).into_response());
+            }
+            Err(rejection) => {
+                body.attempts.push(Attempt {
+                    sql: Some(sql.clone()),
+                    explanation,
+                    error: Some(rejection.error.clone()),
+                    code: Some(rejection.code),
+                });
+
+                let repairs = body.attempts.len() as u32 - 1;
+                if !rejection.repairable() || repairs >= max_repairs {
+                    body.repair_succeeded = (repairs > 0).then_some(false);
+                    body.result = rejection.result;
+                    return Ok(failure(rejection.status, rejection.code, rejection.error, &body));
+                }
+
+                conversation.push(Message::assistant(reply));
+                conversation.push(Message::user(repair_prompt(&schema, &sql, &rejection.error)));
+                last_rejection = Some(rejection);
+            }
+        }
+    }
+}
+
+/// Validates `sql` and either runs it (with `execute`) or prepares it on
+/// the read-only pool. Returns the `/api/sql` body when it ran.
+async fn check(
+    state: &AppState,
+    peer: &ConnectInfo<SocketAddr>,
+    headers: &HeaderMap,
+    sql: &str,
+    b: &Nl2SqlRequest,
+) -> Result<Option<serde_json::Value>, Rejection> {
+    if let Err(reason) = validate_readonly_sql(sql) {
+        return Err(Rejection {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            code: ErrorCode::ForbiddenStatement,
+            error: format!("Generated SQL was rejected: {}", reason),
+            result: None,
+        });
+    }
+
+    if !b.execute {
+        return match placeholder_types(&state.readonly_pool, sql).await {
+            Ok(_) => Ok(None),
+            Err(e) => {
+                let (status, Json(body)) = sql::query_failed(e);
+                Err(Rejection::from_error(status, &body))
+            }
+        };
+    }
+
+    let (status, result) = run_sql(
+        state.clone(),
+        ConnectInfo(peer.0),
+        headers,
+        sql.to_string(),
+        b.max_rows,
+        b.timeout_ms,
+    )
+    .await;
+    if status.is_success() {
+        return Ok(Some(result));
+    }
+
+    Err(Rejection {
+        result: Some(result.clone()),
+        ..Rejection::from_error(status, &result)
+    })
+}
+
+/// The follow-up message for a failed query. The error comes first so it
+/// leads the message whatever the schema excerpt contains.
+fn repair_prompt(schema: &Schema, sql: &str, error: &str) -> String {
+    let tables = referenced_tables(sql);
+    let excerpt = if tables.is_empty() {
+        render_prompt(schema)
+    } else {
+        render_prompt_for(schema, &tables)
+    };
+
+    format!(
+        "The query failed with this error:\n{error}\n\n\
+         Query:\n{sql}\n\n\
+         Relevant schema:\n{excerpt}\n\
+         Reply with a corrected query in the same JSON format."
+    )
+}
+
+/// Tables and views `sql` reads, as written; empty if it doesn't parse.
+fn referenced_tables(sql: &str) -> Vec<String> {
+    let Ok(statements) = Parser::parse_sql(&PostgreSqlDialect {}, sql) else {
+        return vec![];
+    };
+
+    let mut tables = vec![];
+    let _ = visit_relations(&statements, |relation| {
+        let name = relation.0.iter().map(|part| part.value.clone()).collect::<Vec<_>>().join(".");
+        if !tables.contains(&name) {
+            tables.push(name);
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    tables
+}
+
+/// An error response that still carries what was generated so far.
+fn failure(status: StatusCode, code: ErrorCode, error: String, body: &Nl2SqlResponse) -> Response {
+    let mut json = serde_json::json!(body);
+    json["error"] = error.into();
+    json["code"] = code.as_str().into();
+    (status, Json(json)).into_response()
+}
+
+/// Runs `sql` through `/api/sql` as a buffered JSON request on behalf of
+/// the caller, and returns its status and body.
+async fn run_sql(
+    state: AppState,
+    peer: ConnectInfo<SocketAddr>,
+    headers: &HeaderMap,
+    query: String,
+    max_rows: Option<usize>,
+    timeout_ms: Option<u64>,
+) -> (StatusCode, serde_json::Value) {
+    let mut headers = headers.clone();
+    headers.remove(ACCEPT);
+
+    let req = SqlRequest {
+        query,
+        max_rows,
+        timeout_ms,
+        ..Default::default()
+    };
+    let response = sql::execute(State(state), peer, headers, AppJson(req)).await;
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap_or_default();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+/// Reads `{"sql": ..., "explanation": ...}` from the model's reply,
+/// tolerating a markdown code fence around it. A reply that isn't JSON is
+/// taken to be the query itself.
+fn parse_reply(reply: &str) -> (Option<String>, Option<String>) {
+    let mut text = reply.trim();
+    if let Some(fenced) = text.strip_prefix("```") {
+        let body = fenced.split_once('\n').map_or("", |(_, rest)| rest);
+        text = body.trim_end().trim_end_matches("```").trim();
+    }
+
+    let clean = |sql: &str| {
+        let sql = sql.trim().trim_end_matches(';').trim_end();
+        (!sql.is_empty()).then(|| sql.to_string())
+    };
+
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(json) if json.is_object() => (
+            json["sql"].as_str().and_then(clean),
+            json["explanation"].as_str().map(str::to_string),
+        ),
+        _ => (clean(text), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::llm::MockProvider;
+    use crate::test_util;
+
+    #[test]
+    fn parses_json_replies() {
+        assert_eq!(
+            parse_reply(r#"{"sql": "SELECT 1", "explanation": "One."}"#),
+            (Some("SELECT 1".to_string()), Some("One.".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_fenced_replies() {
+        let reply = "```json\n{\"sql\": \"SELECT * FROM vehicles\", \"explanation\": \"All vehicles.\"}\n```";
+        assert_eq!(
+            parse_reply(reply),
+            (Some("SELECT * FROM vehicles".to_string()), Some("All vehicles.".to_string()))
+        );
+        assert_eq!(parse_reply("```sql\nSELECT 1;\n```\n"), (Some("SELECT 1".to_string()), None));
+    }
+
+    #[test]
+    fn takes_bare_replies_as_sql() {
+        assert_eq!(parse_reply("  SELECT count(*) FROM clients\n"), (Some("SELECT count(*) FROM clients".to_string()), None));
+    }
+
+    #[test]
+    fn strips_trailing_semicolons() {
+        assert_eq!(parse_reply(r#"{"sql": "SELECT 1 ;"}"#), (Some("SELECT 1".to_string()), None));
+        assert_eq!(parse_reply("SELECT 1;;"), (Some("SELECT 1".to_string()), None));
+    }
+
+    #[test]
+    fn null_sql_means_unanswerable() {
+        assert_eq!(
+            parse_reply(r#"{"sql": null, "explanation": "No weather data."}"#),
+            (None, Some("No weather data.".to_string()))
+        );
+        assert_eq!(parse_reply(r#"{"sql": ";", "explanation": null}"#), (None, None));
+    }
+
+    #[test]
+    fn only_query_errors_are_repairable() {
+        let rejection = |status, body| Rejection::from_error(status, &body).repairable();
+        let body = |code: &str| serde_json::json!({ "error": "...", "code": code });
+        assert!(rejection(StatusCode::BAD_REQUEST, body("query_failed")));
+        assert!(rejection(StatusCode::FORBIDDEN, body("forbidden_statement")));
+        assert!(!rejection(StatusCode::GATEWAY_TIMEOUT, body("query_timeout")));
+        assert!(!rejection(StatusCode::SERVICE_UNAVAILABLE, body("unavailable")));
+        assert!(!rejection(StatusCode::INTERNAL_SERVER_ERROR, body("internal_error")));
+        assert!(!rejection(StatusCode::BAD_GATEWAY, serde_json::json!({ "error": "Bad Gateway" })));
+    }
+
+    /// Sends `request` to `generate`, with a `MockProvider` answering from
+    /// `fixtures`.
+    async fn ask(
+        fixtures: &[(&str, &str)],
+        request: serde_json::Value,
+    ) -> Option<(StatusCode, serde_json::Value)> {
+        let fixtures = fixtures.iter().map(|(p, sql)| (p.to_string(), sql.to_string())).collect();
+        let state = test_util::state(Arc::new(MockProvider::new(fixtures))).await?;
+        let response = generate(
+            State(state),
+            ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 0))),
+            HeaderMap::new(),
+            AppJson(serde_json::from_value(request).unwrap()),
+        )
+        .await
+        .unwrap();
+
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        Some((status, serde_json::from_slice(&body).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn repairs_a_failing_query() {
+        // The repair prompt leads with the Postgres error, which the first
+        // fixture is keyed on.
+        let fixtures = [
+            ("does not exist", "SELECT count(*) AS vehicles FROM vehicles"),
+            ("fleet size", "SELECT count(no_such_column) FROM vehicles"),
+        ];
+        let Some((status, body)) = ask(&fixtures, serde_json::json!({ "question": "What is our fleet size?" })).await
+        else {
+            return;
+        };
+
+        assert_eq!(status, StatusCode::OK);
+        let attempts = body["attempts"].as_array().unwrap();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0]["code"], "query_failed");
+        assert!(attempts[0]["error"].as_str().unwrap().contains("no_such_column"));
+        assert_eq!(attempts[1]["error"], serde_json::Value::Null);
+        assert_eq!(body["sql"], "SELECT count(*) AS vehicles FROM vehicles");
+        assert_eq!(body["repair_succeeded"], true);
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_repairs() {
+        let fixtures = [("", "SELECT no_such_column FROM vehicles")];
+        let request = serde_json::json!({ "question": "Anything", "max_repairs": 1 });
+        let Some((status, body)) = ask(&fixtures, request).await else {
+            return;
+        };
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "query_failed");
+        assert_eq!(body["attempts"].as_array().unwrap().len(), 2);
+        assert_eq!(body["repair_succeeded"], false);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_timeouts() {
+        let fixtures = [("", "SELECT pg_sleep(1)")];
+        let request = serde_json::json!({ "question": "Anything", "execute": true, "timeout_ms": 50 });
+        let Some((status, body)) = ask(&fixtures, request).await else {
+            return;
+        };
+
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body["code"], "query_timeout");
+        assert_eq!(body["attempts"].as_array().unwrap().len(), 1);
+        assert_eq!(body["repair_succeeded"], serde_json::Value::Null);
+    }
+}
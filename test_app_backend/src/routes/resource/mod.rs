@@ -0,0 +1,226 @@
+//! Generic CRUD handlers. A table becomes `/api/{PATH}` and
+//! `/api/{PATH}/{id}` by implementing `Resource` for its row struct and
+//! merging `routes::<Row>()` into the router.
+
+use std::future::Future;
+
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
+
+use super::column::{Access, Bound, Column, FieldType};
+use super::list::{ListPage, ListQuery, ListSpec};
+use super::AppState;
+use crate::error::{AppError, FieldError};
+use crate::extract::{AppJson, AppPath};
+use crate::validate::{Validate, Validator};
+
+mod tables;
+
+/// A table served through the generic CRUD handlers, implemented by the
+/// struct its rows are read into.
+pub trait Resource: for<'r> FromRow<'r, PgRow> + Serialize + Send + Sync + Unpin + 'static {
+    /// Path segment under `/api/`.
+    const PATH: &'static str;
+    const TABLE: &'static str;
+    /// Every column; list requests may sort and filter on any of them.
+    /// Writable columns missing from a create or replace body are set to
+    /// their default.
+    const COLUMNS: &'static [Column];
+
+    /// Body of `POST` and `PUT`. Its fields must be named after the
+    /// writable columns.
+    type Create: DeserializeOwned + Validate + Send + Sync + 'static;
+
+    /// Rules that need the database, run after `Create`'s own validation
+    /// passes.
+    fn check(_pool: &PgPool, _body: &Self::Create) -> impl Future<Output = Result<(), AppError>> + Send {
+        async { Ok(()) }
+    }
+}
+
+pub fn routes<R: Resource>() -> Router<AppState> {
+    let collection = format!("/api/{}", R::PATH);
+    let item = format!("{}/{{id}}", collection);
+    Router::new()
+        .route(&collection, get(list::<R>).post(create::<R>))
+        .route(&item, get(get_one::<R>).put(update::<R>).patch(patch::<R>).delete(delete::<R>))
+}
+
+async fn list<R: Resource>(State(pool): State<PgPool>, query: ListQuery) -> Result<ListPage<R>, AppError> {
+    let spec = ListSpec { table: R::TABLE, columns: R::COLUMNS };
+    spec.fetch(&pool, &query).await
+}
+
+async fn get_one<R: Resource>(State(pool): State<PgPool>, AppPath(id): AppPath<i32>) -> Result<Json<R>, AppError> {
+    let row = sqlx::query_as::<_, R>(&format!("SELECT * FROM {} WHERE id = $1", R::TABLE))
+        .bind(id)
+        .fetch_one(&pool)
+        .await?;
+    Ok(Json(row))
+}
+
+async fn create<R: Resource>(State(pool): State<PgPool>, AppJson(b): AppJson<Value>) -> Result<Json<R>, AppError> {
+    let values = read_body::<R>(&pool, b).await?;
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!("INSERT INTO {} (", R::TABLE));
+    let mut names = qb.separated(", ");
+    for (column, _) in &values {
+        names.push(column.name);
+    }
+    qb.push(") VALUES (");
+    for (i, (_, value)) in values.iter().enumerate() {
+        if i > 0 {
+            qb.push(", ");
+        }
+        push_value(&mut qb, value.as_ref());
+    }
+    qb.push(") RETURNING *");
+
+    Ok(Json(qb.build_query_as().fetch_one(&pool).await?))
+}
+
+/// `PUT`: replaces every writable column.
+async fn update<R: Resource>(
+    State(pool): State<PgPool>,
+    AppPath(id): AppPath<i32>,
+    AppJson(b): AppJson<Value>,
+) -> Result<Json<R>, AppError> {
+    let values = read_body::<R>(&pool, b).await?;
+    let values = values.iter().map(|(column, value)| (*column, value.as_ref()));
+    Ok(Json(update_row(&pool, id, values).await?))
+}
+
+/// `PATCH`: updates only the columns present in the body. A field set to
+/// `null` clears the column; a field left out keeps its value. Every field
+/// is checked before the row is touched, so a bad request reports all its
+/// problems at once, and the patched row must pass the same validation as
+/// a `PUT`. An empty body returns the row unchanged.
+async fn patch<R: Resource>(
+    State(pool): State<PgPool>,
+    AppPath(id): AppPath<i32>,
+    AppJson(b): AppJson<Map<String, Value>>,
+) -> Result<Json<R>, AppError> {
+    let mut issues = vec![];
+    let mut values = vec![];
+    for (field, value) in &b {
+        let Some(column) = R::COLUMNS.iter().find(|c| c.name == field && c.access != Access::ReadOnly) else {
+            issues.push(FieldError::new(field, "can't be updated"));
+            continue;
+        };
+        if value.is_null() && column.access != Access::Nullable {
+            issues.push(FieldError::new(field, "must not be null"));
+            continue;
+        }
+        match Bound::from_json(column.ty, value) {
+            Ok(bound) => values.push((column, bound)),
+            Err(e) => issues.push(FieldError::new(field, e)),
+        }
+    }
+    if !issues.is_empty() {
+        return Err(AppError::validation(issues));
+    }
+
+    if values.is_empty() {
+        return get_one::<R>(State(pool), AppPath(id)).await;
+    }
+    let current = get_one::<R>(State(pool.clone()), AppPath(id)).await?.0;
+    validate::<R>(&pool, &patched::<R>(&current, &b)?).await?;
+
+    let values = values.iter().map(|(column, value)| (*column, Some(value)));
+    Ok(Json(update_row(&pool, id, values).await?))
+}
+
+async fn delete<R: Resource>(State(pool): State<PgPool>, AppPath(id): AppPath<i32>) -> Result<Json<Value>, AppError> {
+    sqlx::query(&format!("DELETE FROM {} WHERE id = $1", R::TABLE))
+        .bind(id)
+        .execute(&pool)
+        .await?;
+    Ok(Json(serde_json::json!({"deleted": id})))
+}
+
+async fn validate<R: Resource>(pool: &PgPool, body: &R::Create) -> Result<(), AppError> {
+    Validator::check(body)?;
+    R::check(pool, body).await
+}
+
+/// `row` with `patch` applied, read as a replace body.
+fn patched<R: Resource>(row: &R, patch: &Map<String, Value>) -> Result<R::Create, AppError> {
+    let mut json = serde_json::to_value(row).map_err(|e| AppError::internal(e.to_string()))?;
+    for (field, value) in patch {
+        // Decimals are read from strings, and patch bodies may send numbers.
+        let decimal = R::COLUMNS.iter().any(|c| c.name == field && matches!(c.ty, FieldType::Decimal));
+        json[field] = match value {
+            Value::Number(n) if decimal => Value::String(n.to_string()),
+            value => value.clone(),
+        };
+    }
+    serde_json::from_value(json).map_err(|e| AppError::internal(e.to_string()))
+}
+
+/// Checks a create or replace body and returns a value for every writable
+/// column; `None` where the body left it out, so the column gets its
+/// default. The body is read column by column first, so a value of the
+/// wrong type or outside a column's choices is reported against its field.
+async fn read_body<R: Resource>(pool: &PgPool, json: Value) -> Result<Vec<(&'static Column, Option<Bound>)>, AppError> {
+    let values = full_values::<R>(&json)?;
+    let body = serde_json::from_value::<R::Create>(json).map_err(|e| {
+        let message = e.to_string();
+        match message.strip_prefix("missing field `").and_then(|rest| rest.split_once('`')) {
+            Some((field, _)) => AppError::validation(vec![FieldError::new(field, "is required")]),
+            None => AppError::bad_request(message),
+        }
+    })?;
+    validate::<R>(pool, &body).await?;
+    Ok(values)
+}
+
+fn full_values<R: Resource>(json: &Value) -> Result<Vec<(&'static Column, Option<Bound>)>, AppError> {
+    let mut issues = vec![];
+    let mut values = vec![];
+    for column in R::COLUMNS.iter().filter(|c| c.access != Access::ReadOnly) {
+        match &json[column.name] {
+            Value::Null => values.push((column, None)),
+            value => match Bound::from_json(column.ty, value) {
+                Ok(bound) => values.push((column, Some(bound))),
+                Err(e) => issues.push(FieldError::new(column.name, e)),
+            },
+        }
+    }
+    if !issues.is_empty() {
+        return Err(AppError::validation(issues));
+    }
+    Ok(values)
+}
+
+fn push_value(qb: &mut QueryBuilder<'_, Postgres>, value: Option<&Bound>) {
+    match value {
+        Some(value) => value.push(qb),
+        None => {
+            qb.push("DEFAULT");
+        }
+    }
+}
+
+async fn update_row<'a, R: Resource>(
+    pool: &PgPool,
+    id: i32,
+    values: impl Iterator<Item = (&'a Column, Option<&'a Bound>)>,
+) -> Result<R, AppError> {
+    let mut qb = QueryBuilder::<Postgres>::new(format!("UPDATE {} SET ", R::TABLE));
+    for (i, (column, value)) in values.enumerate() {
+        if i > 0 {
+            qb.push(", ");
+        }
+        qb.push(column.name).push(" = ");
+        push_value(&mut qb, value);
+    }
+    qb.push(" WHERE id = ").push_bind(id).push(" RETURNING *");
+
+    Ok(qb.build_query_as().fetch_one(pool).await?)
+}
@@ -0,0 +1,176 @@
+//! The car rental tables served as resources.
+
+use sqlx::PgPool;
+
+use super::Resource;
+use crate::error::{AppError, FieldError};
+use crate::models::{
+    Client, CreateClient, CreateEmployee, CreateLocation, CreateMaintenanceRecord, CreatePayment,
+    CreateReservation, CreateReview, CreateVehicle, CreateVehicleCategory, Employee, EmployeeRole, Location,
+    MaintenanceRecord, MaintenanceType, Payment, PaymentMethod, PaymentStatus, Reservation, ReservationStatus,
+    Review, Vehicle, VehicleCategory, VehicleStatus,
+};
+use crate::routes::column::Column;
+use crate::routes::column::FieldType::{Bool, Choice, Date, Decimal, Int, Text, Timestamp};
+
+impl Resource for Location {
+    const PATH: &'static str = "locations";
+    const TABLE: &'static str = "locations";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("name", Text),
+        Column::writable("city", Text),
+        Column::writable("address", Text),
+        Column::nullable("phone", Text),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreateLocation;
+}
+
+impl Resource for Employee {
+    const PATH: &'static str = "employees";
+    const TABLE: &'static str = "employees";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("location_id", Int),
+        Column::writable("first_name", Text),
+        Column::writable("last_name", Text),
+        Column::writable("role", Choice(EmployeeRole::VALUES)),
+        Column::writable("salary", Decimal),
+        Column::writable("hire_date", Date),
+        Column::nullable("email", Text),
+        Column::nullable("phone", Text),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreateEmployee;
+}
+
+impl Resource for VehicleCategory {
+    const PATH: &'static str = "categories";
+    const TABLE: &'static str = "vehicle_categories";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("name", Text),
+        Column::nullable("description", Text),
+        Column::writable("daily_rate_min", Decimal),
+        Column::writable("daily_rate_max", Decimal),
+    ];
+    type Create = CreateVehicleCategory;
+}
+
+impl Resource for Vehicle {
+    const PATH: &'static str = "vehicles";
+    const TABLE: &'static str = "vehicles";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("category_id", Int),
+        Column::writable("location_id", Int),
+        Column::writable("make", Text),
+        Column::writable("model", Text),
+        Column::writable("year", Int),
+        Column::writable("license_plate", Text),
+        Column::nullable("color", Text),
+        Column::writable("daily_rate", Decimal),
+        Column::writable("mileage", Int),
+        Column::writable("status", Choice(VehicleStatus::VALUES)),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreateVehicle;
+
+    /// `daily_rate` must lie within the category's range. A missing
+    /// category is left to the foreign key.
+    async fn check(pool: &PgPool, body: &CreateVehicle) -> Result<(), AppError> {
+        let range: Option<(rust_decimal::Decimal, rust_decimal::Decimal)> =
+            sqlx::query_as("SELECT daily_rate_min, daily_rate_max FROM vehicle_categories WHERE id = $1")
+                .bind(body.category_id)
+                .fetch_optional(pool)
+                .await?;
+        match range {
+            Some((min, max)) if !(min..=max).contains(&body.daily_rate) => Err(AppError::validation(vec![
+                FieldError::new("daily_rate", format!("must be between {} and {} for this category", min, max)),
+            ])),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Resource for Client {
+    const PATH: &'static str = "clients";
+    const TABLE: &'static str = "clients";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("first_name", Text),
+        Column::writable("last_name", Text),
+        Column::writable("email", Text),
+        Column::nullable("phone", Text),
+        Column::writable("drivers_license", Text),
+        Column::writable("date_of_birth", Date),
+        Column::writable("registration_date", Date),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreateClient;
+}
+
+impl Resource for Reservation {
+    const PATH: &'static str = "reservations";
+    const TABLE: &'static str = "reservations";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("client_id", Int),
+        Column::writable("vehicle_id", Int),
+        Column::writable("pickup_location", Int),
+        Column::writable("return_location", Int),
+        Column::writable("pickup_date", Date),
+        Column::writable("return_date", Date),
+        Column::writable("status", Choice(ReservationStatus::VALUES)),
+        Column::nullable("total_cost", Decimal),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreateReservation;
+}
+
+impl Resource for Payment {
+    const PATH: &'static str = "payments";
+    const TABLE: &'static str = "payments";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("reservation_id", Int),
+        Column::writable("amount", Decimal),
+        Column::writable("payment_method", Choice(PaymentMethod::VALUES)),
+        Column::writable("payment_date", Date),
+        Column::writable("status", Choice(PaymentStatus::VALUES)),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreatePayment;
+}
+
+impl Resource for MaintenanceRecord {
+    const PATH: &'static str = "maintenance";
+    const TABLE: &'static str = "maintenance_records";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("vehicle_id", Int),
+        Column::writable("maintenance_type", Choice(MaintenanceType::VALUES)),
+        Column::nullable("description", Text),
+        Column::writable("cost", Decimal),
+        Column::writable("maintenance_date", Date),
+        Column::nullable("mileage_at_service", Int),
+        Column::nullable("completed", Bool),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreateMaintenanceRecord;
+}
+
+impl Resource for Review {
+    const PATH: &'static str = "reviews";
+    const TABLE: &'static str = "reviews";
+    const COLUMNS: &'static [Column] = &[
+        Column::read_only("id", Int),
+        Column::writable("reservation_id", Int),
+        Column::writable("rating", Int),
+        Column::nullable("comment", Text),
+        Column::writable("review_date", Date),
+        Column::read_only("created_at", Timestamp),
+    ];
+    type Create = CreateReview;
+}
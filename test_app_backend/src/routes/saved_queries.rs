@@ -0,0 +1,229 @@
+use axum::body::Body;
+use axum::extract::{ConnectInfo, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::Json;
+use futures_util::{stream, StreamExt};
+use serde::Deserialize;
+use sqlx::{PgPool, TypeInfo};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use super::sql::{self, placeholder_types, validate_readonly_sql, SqlRequest, StreamFormat};
+use super::AppState;
+use crate::error::{AppError, ErrorCode, FieldError};
+use crate::extract::{AppJson, AppPath};
+use crate::models::{CreateSavedQuery, SavedQuery};
+
+/// Arguments for running a saved query, keyed by declared parameter name.
+/// The remaining fields behave as they do on `/api/sql`.
+#[derive(Deserialize)]
+pub struct RunSavedQuery {
+    #[serde(default)]
+    args: serde_json::Map<String, serde_json::Value>,
+    max_rows: Option<usize>,
+    timeout_ms: Option<u64>,
+    page_size: Option<usize>,
+    cursor: Option<String>,
+}
+
+pub async fn list(State(pool): State<PgPool>) -> Result<Json<Vec<SavedQuery>>, AppError> {
+    let rows = sqlx::query_as::<_, SavedQuery>("SELECT * FROM saved_queries ORDER BY name")
+        .fetch_all(&pool)
+        .await?;
+    Ok(Json(rows))
+}
+
+pub async fn get_one(State(pool): State<PgPool>, AppPath(name): AppPath<String>) -> Result<Json<SavedQuery>, AppError> {
+    let row = sqlx::query_as::<_, SavedQuery>("SELECT * FROM saved_queries WHERE name = $1")
+        .bind(&name)
+        .fetch_one(&pool)
+        .await?;
+    Ok(Json(row))
+}
+
+pub async fn create(State(state): State<AppState>, AppJson(b): AppJson<CreateSavedQuery>) -> Result<Json<SavedQuery>, AppError> {
+    let b = check(&state.readonly_pool, b).await?;
+    let row = sqlx::query_as::<_, SavedQuery>(
+        "INSERT INTO saved_queries (name, description, query, params) \
+         VALUES ($1,$2,$3,$4) RETURNING *",
+    )
+    .bind(&b.name)
+    .bind(&b.description)
+    .bind(&b.query)
+    .bind(sqlx::types::Json(&b.params))
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| name_taken(e, &b.name))?;
+    Ok(Json(row))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    AppPath(name): AppPath<String>,
+    AppJson(b): AppJson<CreateSavedQuery>,
+) -> Result<Json<SavedQuery>, AppError> {
+    let b = check(&state.readonly_pool, b).await?;
+    let row = sqlx::query_as::<_, SavedQuery>(
+        "UPDATE saved_queries SET name=$1, description=$2, query=$3, params=$4, updated_at=NOW() \
+         WHERE name=$5 RETURNING *",
+    )
+    .bind(&b.name)
+    .bind(&b.description)
+    .bind(&b.query)
+    .bind(sqlx::types::Json(&b.params))
+    .bind(&name)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| name_taken(e, &b.name))?;
+    Ok(Json(row))
+}
+
+pub async fn delete(State(pool): State<PgPool>, AppPath(name): AppPath<String>) -> Result<Json<serde_json::Value>, AppError> {
+    sqlx::query("DELETE FROM saved_queries WHERE name = $1")
+        .bind(&name)
+        .execute(&pool)
+        .await?;
+    Ok(Json(serde_json::json!({"deleted": name})))
+}
+
+/// Runs a saved query through `/api/sql`'s read-only path, so limits,
+/// timeouts, the cost guard and `Accept`-based streaming all apply. Each
+/// successful run records when it happened and how long it took; for a
+/// streamed run that is until the last row was sent, and a stream the
+/// client abandons isn't recorded. A result served from the cache only
+/// updates `last_run_at`, since its timing says nothing about the query.
+pub async fn run(
+    State(state): State<AppState>,
+    AppPath(name): AppPath<String>,
+    peer: ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    AppJson(b): AppJson<RunSavedQuery>,
+) -> Result<Response, AppError> {
+    let saved = sqlx::query_as::<_, SavedQuery>("SELECT * FROM saved_queries WHERE name = $1")
+        .bind(&name)
+        .fetch_one(&state.pool)
+        .await?;
+
+    if let Some(unknown) = b.args.keys().find(|k| !saved.params.iter().any(|p| &p.name == *k)) {
+        return Err(AppError::bad_request(format!(
+            "Saved query '{}' has no parameter named '{}'",
+            name, unknown
+        )));
+    }
+
+    let mut params = Vec::with_capacity(saved.params.len());
+    for param in saved.params.iter() {
+        match b.args.get(&param.name) {
+            Some(value) => params.push(value.clone()),
+            None => {
+                return Err(AppError::validation(vec![FieldError::new(
+                    format!("args.{}", param.name),
+                    "is required",
+                )]))
+            }
+        }
+    }
+
+    let req = SqlRequest {
+        query: saved.query,
+        params,
+        max_rows: b.max_rows,
+        timeout_ms: b.timeout_ms,
+        page_size: b.page_size,
+        cursor: b.cursor,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let streamed = StreamFormat::from_headers(&headers).is_some();
+    let response = sql::execute(State(state.clone()), peer, headers, AppJson(req)).await;
+
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+    if !streamed {
+        let cached = response.headers().get("x-cache").is_some_and(|v| v == "HIT");
+        let duration_ms = (!cached).then(|| elapsed_ms(start));
+        let pool = state.pool.clone();
+        tokio::spawn(async move { record_run(&pool, saved.id, &name, duration_ms).await });
+        return Ok(response);
+    }
+
+    // Rows are still being sent; record the run once the body ends. A body
+    // that fails or is dropped part way is never polled to the end.
+    let (parts, body) = response.into_parts();
+    let pool = state.pool.clone();
+    let finished = stream::once(async move { record_run(&pool, saved.id, &name, Some(elapsed_ms(start))).await })
+        .filter_map(|()| async { None });
+    let body = Body::from_stream(body.into_data_stream().chain(finished));
+    Ok(Response::from_parts(parts, body))
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Stamps `last_run_at`, and `last_duration_ms` when `duration_ms` is given.
+async fn record_run(pool: &PgPool, id: i32, name: &str, duration_ms: Option<f64>) {
+    let recorded = sqlx::query(
+        "UPDATE saved_queries SET last_run_at = NOW(), \
+         last_duration_ms = COALESCE($1, last_duration_ms) WHERE id = $2",
+    )
+    .bind(duration_ms)
+    .bind(id)
+    .execute(pool)
+    .await;
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record run of saved query '{}': {}", name, e);
+    }
+}
+
+/// Rejects anything `/api/sql` would refuse to run and makes sure every
+/// `$n` placeholder has a declared parameter, recording the type Postgres
+/// infers for each.
+async fn check(readonly_pool: &PgPool, mut b: CreateSavedQuery) -> Result<CreateSavedQuery, AppError> {
+    let invalid = |field: &str, msg: String| AppError::validation(vec![FieldError::new(field, msg)]);
+
+    if b.name.is_empty() || !b.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(invalid(
+            "name",
+            "must be non-empty and use only letters, digits, '_' and '-'".into(),
+        ));
+    }
+
+    b.query = b.query.trim().to_string();
+    validate_readonly_sql(&b.query).map_err(|reason| invalid("query", reason))?;
+
+    // Only the database's verdict on the query is the caller's to fix.
+    let types = placeholder_types(readonly_pool, &b.query).await.map_err(|e| match e {
+        sqlx::Error::Database(_) => invalid("query", e.to_string()),
+        e => AppError::from(e),
+    })?;
+    if types.len() != b.params.len() {
+        return Err(invalid("params", format!(
+            "Query has {} placeholder(s) but {} parameter(s) are declared",
+            types.len(),
+            b.params.len()
+        )));
+    }
+
+    for (i, param) in b.params.iter().enumerate() {
+        if b.params[..i].iter().any(|p| p.name == param.name) {
+            return Err(invalid("params", format!("Parameter '{}' is declared twice", param.name)));
+        }
+    }
+
+    for (param, type_info) in b.params.iter_mut().zip(&types) {
+        param.pg_type = Some(type_info.name().to_lowercase());
+    }
+    Ok(b)
+}
+
+fn name_taken(e: sqlx::Error, name: &str) -> AppError {
+    let err = AppError::from(e);
+    match err.code {
+        ErrorCode::UniqueViolation => err.with_message(format!("A saved query named '{}' already exists", name)),
+        _ => err,
+    }
+}
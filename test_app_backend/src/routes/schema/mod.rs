@@ -0,0 +1,332 @@
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::types::Oid;
+use sqlx::PgPool;
+
+use super::AppState;
+use crate::error::AppError;
+use crate::extract::AppQuery;
+
+mod prompt;
+mod stats;
+
+pub use prompt::{render_prompt, render_prompt_for};
+pub use stats::{get_stats, StatsCache};
+
+/// Everything the read-only SQL role can query, as seen through the catalog.
+#[derive(Serialize)]
+pub struct Schema {
+    pub database: String,
+    pub tables: Vec<Table>,
+}
+
+#[derive(Serialize)]
+pub struct Table {
+    pub schema: String,
+    pub name: String,
+    /// `table`, `view`, `materialized_view` or `partitioned_table`.
+    pub kind: &'static str,
+    pub comment: Option<String>,
+    pub columns: Vec<Column>,
+    pub primary_key: Vec<String>,
+    pub unique: Vec<Vec<String>>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub checks: Vec<Check>,
+    pub indexes: Vec<Index>,
+    #[serde(skip)]
+    oid: Oid,
+}
+
+#[derive(Serialize)]
+pub struct Column {
+    pub name: String,
+    /// Type as Postgres prints it, e.g. `character varying(20)`.
+    pub data_type: String,
+    pub nullable: bool,
+    pub default: Option<String>,
+    pub comment: Option<String>,
+    /// Values allowed by an enum type or a single-column `IN (...)` CHECK.
+    pub allowed_values: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct ForeignKey {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub references_table: String,
+    pub references_columns: Vec<String>,
+    /// `CASCADE`, `RESTRICT`, `SET NULL`, `SET DEFAULT` or `NO ACTION`.
+    pub on_delete: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub definition: String,
+}
+
+#[derive(Serialize)]
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub primary: bool,
+    pub definition: String,
+}
+
+#[derive(Deserialize)]
+pub struct SchemaQuery {
+    /// `text` returns the compact prompt rendering instead of JSON.
+    format: Option<String>,
+}
+
+pub async fn get_schema(
+    State(state): State<AppState>,
+    AppQuery(q): AppQuery<SchemaQuery>,
+) -> Result<Response, AppError> {
+    let schema = introspect(&state.readonly_pool).await?;
+
+    match q.format.as_deref() {
+        Some("text") => Ok((
+            [(CONTENT_TYPE, "text/plain; charset=utf-8")],
+            render_prompt(&schema),
+        )
+            .into_response()),
+        _ => Ok(Json(schema).into_response()),
+    }
+}
+
+/// Reads the catalog through `pool`. Only relations the connected role can
+/// `SELECT` from are included, so pointing this at the read-only pool gives
+/// exactly what `/api/sql` can see.
+pub async fn introspect(pool: &PgPool) -> Result<Schema, sqlx::Error> {
+    let database: String = sqlx::query_scalar("SELECT current_database()::text")
+        .fetch_one(pool)
+        .await?;
+
+    let mut tables: Vec<Table> = sqlx::query_as::<_, (Oid, String, String, String, Option<String>)>(
+        "SELECT c.oid, n.nspname::text, c.relname::text, c.relkind::text, \
+                obj_description(c.oid, 'pg_class') \
+         FROM pg_catalog.pg_class c \
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE c.relkind IN ('r', 'p', 'v', 'm') \
+           AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+           AND n.nspname NOT LIKE 'pg_toast%' \
+           AND has_table_privilege(c.oid, 'SELECT') \
+         ORDER BY n.nspname, c.relname",
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(oid, schema, name, relkind, comment)| Table {
+        schema,
+        name,
+        kind: match relkind.as_str() {
+            "v" => "view",
+            "m" => "materialized_view",
+            "p" => "partitioned_table",
+            _ => "table",
+        },
+        comment,
+        columns: vec![],
+        primary_key: vec![],
+        unique: vec![],
+        foreign_keys: vec![],
+        checks: vec![],
+        indexes: vec![],
+        oid,
+    })
+    .collect();
+
+    let oids: Vec<Oid> = tables.iter().map(|t| t.oid).collect();
+
+    let columns = sqlx::query_as::<
+        _,
+        (Oid, String, String, bool, Option<String>, Option<String>, Option<Vec<String>>),
+    >(
+        "SELECT a.attrelid, a.attname::text, format_type(a.atttypid, a.atttypmod), \
+                NOT a.attnotnull, pg_get_expr(d.adbin, d.adrelid), \
+                col_description(a.attrelid, a.attnum), \
+                (SELECT array_agg(e.enumlabel::text ORDER BY e.enumsortorder) \
+                 FROM pg_catalog.pg_enum e WHERE e.enumtypid = a.atttypid) \
+         FROM pg_catalog.pg_attribute a \
+         LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+         WHERE a.attrelid = ANY($1) AND a.attnum > 0 AND NOT a.attisdropped \
+         ORDER BY a.attrelid, a.attnum",
+    )
+    .bind(&oids)
+    .fetch_all(pool)
+    .await?;
+
+    for (oid, name, data_type, nullable, default, comment, enum_values) in columns {
+        if let Some(table) = tables.iter_mut().find(|t| t.oid == oid) {
+            table.columns.push(Column {
+                name,
+                data_type,
+                nullable,
+                default,
+                comment,
+                allowed_values: enum_values,
+            });
+        }
+    }
+
+    let constraints = sqlx::query_as::<_, (Oid, String, String, Vec<String>, Option<String>, Vec<String>, String, String)>(
+        "SELECT c.conrelid, c.conname::text, c.contype::text, \
+                ARRAY(SELECT a.attname::text FROM unnest(c.conkey) WITH ORDINALITY k(num, ord) \
+                      JOIN pg_catalog.pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.num \
+                      ORDER BY k.ord), \
+                CASE WHEN c.contype = 'f' THEN c.confrelid::regclass::text END, \
+                ARRAY(SELECT a.attname::text FROM unnest(c.confkey) WITH ORDINALITY k(num, ord) \
+                      JOIN pg_catalog.pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.num \
+                      ORDER BY k.ord), \
+                c.confdeltype::text, \
+                pg_get_constraintdef(c.oid) \
+         FROM pg_catalog.pg_constraint c \
+         WHERE c.conrelid = ANY($1) AND c.contype IN ('p', 'u', 'f', 'c') \
+         ORDER BY c.conrelid, c.conname",
+    )
+    .bind(&oids)
+    .fetch_all(pool)
+    .await?;
+
+    for (oid, name, kind, columns, references_table, references_columns, on_delete, definition) in constraints {
+        let Some(table) = tables.iter_mut().find(|t| t.oid == oid) else {
+            continue;
+        };
+        match kind.as_str() {
+            "p" => table.primary_key = columns,
+            "u" => table.unique.push(columns),
+            "f" => table.foreign_keys.push(ForeignKey {
+                name,
+                columns,
+                references_table: references_table.unwrap_or_default(),
+                references_columns,
+                on_delete: match on_delete.as_str() {
+                    "c" => "CASCADE",
+                    "r" => "RESTRICT",
+                    "n" => "SET NULL",
+                    "d" => "SET DEFAULT",
+                    _ => "NO ACTION",
+                },
+            }),
+            _ => {
+                if let [column] = columns.as_slice() {
+                    if let Some(values) = check_values(&definition) {
+                        if let Some(col) = table.columns.iter_mut().find(|c| &c.name == column) {
+                            col.allowed_values = Some(values);
+                        }
+                    }
+                }
+                table.checks.push(Check {
+                    name,
+                    columns,
+                    definition,
+                });
+            }
+        }
+    }
+
+    let indexes = sqlx::query_as::<_, (Oid, String, bool, bool, Vec<String>, String)>(
+        "SELECT i.indrelid, ic.relname::text, i.indisunique, i.indisprimary, \
+                ARRAY(SELECT pg_get_indexdef(i.indexrelid, k, true) \
+                      FROM generate_series(1, i.indnkeyatts) k ORDER BY k), \
+                pg_get_indexdef(i.indexrelid) \
+         FROM pg_catalog.pg_index i \
+         JOIN pg_catalog.pg_class ic ON ic.oid = i.indexrelid \
+         WHERE i.indrelid = ANY($1) \
+         ORDER BY i.indrelid, ic.relname",
+    )
+    .bind(&oids)
+    .fetch_all(pool)
+    .await?;
+
+    for (oid, name, unique, primary, columns, definition) in indexes {
+        if let Some(table) = tables.iter_mut().find(|t| t.oid == oid) {
+            table.indexes.push(Index {
+                name,
+                columns,
+                unique,
+                primary,
+                definition,
+            });
+        }
+    }
+
+    Ok(Schema { database, tables })
+}
+
+/// Pulls the literals out of the form Postgres stores `col IN ('a', 'b')`
+/// as: `CHECK (((col)::text = ANY ((ARRAY['a'::character varying, ...])::text[])))`.
+fn check_values(definition: &str) -> Option<Vec<String>> {
+    let start = definition.find("= ANY")?;
+    let array = &definition[start..];
+    let array = &array[array.find("ARRAY[")? + "ARRAY[".len()..];
+
+    // Quoted literals may contain `,` and `]`, so the array ends at the
+    // first `]` outside quotes. Casts between the literals are skipped.
+    let mut values = vec![];
+    let mut chars = array.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ']' => break,
+            '\'' => {
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\'' if chars.peek() == Some(&'\'') => {
+                            value.push('\'');
+                            chars.next();
+                        }
+                        '\'' => break,
+                        c => value.push(c),
+                    }
+                }
+                values.push(value);
+            }
+            _ => {}
+        }
+    }
+
+    (!values.is_empty()).then_some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_values;
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    #[test]
+    fn reads_varchar_in_list() {
+        let def = "CHECK (((status)::text = ANY ((ARRAY['available'::character varying, \
+                   'rented'::character varying, 'no_show'::character varying])::text[])))";
+        assert_eq!(check_values(def), strings(&["available", "rented", "no_show"]));
+    }
+
+    #[test]
+    fn reads_text_in_list() {
+        assert_eq!(check_values("CHECK ((b = ANY (ARRAY['one'::text, 'two'::text])))"), strings(&["one", "two"]));
+    }
+
+    #[test]
+    fn keeps_commas_quotes_and_brackets_inside_values() {
+        let def = "CHECK (((a)::text = ANY ((ARRAY['x, y'::character varying, 'it''s'::character varying, \
+                   'br]ck'::character varying, 'p'::character varying])::text[])))";
+        assert_eq!(check_values(def), strings(&["x, y", "it's", "br]ck", "p"]));
+    }
+
+    #[test]
+    fn ignores_checks_that_are_not_string_in_lists() {
+        assert_eq!(check_values("CHECK (((year >= 2015) AND (year <= 2025)))"), None);
+        assert_eq!(check_values("CHECK ((rating >= 1))"), None);
+        assert_eq!(check_values("CHECK ((b = 'q'::text))"), None);
+        assert_eq!(check_values("CHECK ((c = ANY (ARRAY[1, 2])))"), None);
+    }
+}
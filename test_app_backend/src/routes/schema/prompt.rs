@@ -0,0 +1,110 @@
+use super::{Column, Schema, Table};
+
+/// Renders the schema as compact text for an LLM prompt: one line per table
+/// listing its columns with types, keys, references and allowed values,
+/// followed by table comments, multi-column checks and indexed columns.
+pub fn render_prompt(schema: &Schema) -> String {
+    render_tables(schema, |_| true)
+}
+
+/// Like `render_prompt`, limited to the named tables and the tables they
+/// reference through foreign keys. Names may be schema-qualified.
+pub fn render_prompt_for(schema: &Schema, names: &[String]) -> String {
+    let named = |t: &Table| {
+        names
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(&t.name) || n.eq_ignore_ascii_case(&format!("{}.{}", t.schema, t.name)))
+    };
+    let referenced: Vec<String> = schema
+        .tables
+        .iter()
+        .filter(|t| named(t))
+        .flat_map(|t| t.foreign_keys.iter().map(|fk| fk.references_table.clone()))
+        .collect();
+
+    render_tables(schema, |t| named(t) || referenced.iter().any(|r| r == &t.name))
+}
+
+fn render_tables(schema: &Schema, include: impl Fn(&Table) -> bool) -> String {
+    let mut out = format!("Database: {} (PostgreSQL)\n\nTables:\n", schema.database);
+
+    for table in schema.tables.iter().filter(|t| include(t)) {
+        out.push('\n');
+        out.push_str(&render_table(table));
+    }
+
+    out
+}
+
+fn render_table(table: &Table) -> String {
+    let name = if table.schema == "public" {
+        table.name.clone()
+    } else {
+        format!("{}.{}", table.schema, table.name)
+    };
+    let prefix = if table.kind == "table" { "" } else { "view " };
+
+    let columns: Vec<String> = table.columns.iter().map(|c| render_column(table, c)).collect();
+    let mut out = format!("{}{}({})\n", prefix, name, columns.join(", "));
+
+    if let Some(comment) = &table.comment {
+        out.push_str(&format!("  - {}\n", comment));
+    }
+
+    for check in table.checks.iter().filter(|c| c.columns.len() > 1) {
+        out.push_str(&format!("  - {}\n", check.definition));
+    }
+
+    let indexed: Vec<String> = table
+        .indexes
+        .iter()
+        .filter(|i| !i.primary)
+        .map(|i| i.columns.join("+"))
+        .collect();
+    if !indexed.is_empty() {
+        out.push_str(&format!("  - indexed: {}\n", indexed.join(", ")));
+    }
+
+    out
+}
+
+fn render_column(table: &Table, column: &Column) -> String {
+    let mut out = format!("{} {}", column.name, short_type(&column.data_type));
+    let single = |cols: &[String]| cols.len() == 1 && cols[0] == column.name;
+
+    if single(&table.primary_key) {
+        out.push_str(" PK");
+    }
+    if table.unique.iter().any(|u| single(u)) {
+        out.push_str(" UNIQUE");
+    }
+    for fk in table.foreign_keys.iter().filter(|fk| single(&fk.columns)) {
+        out.push_str(&format!(" FK→{}", fk.references_table));
+    }
+
+    match &column.allowed_values {
+        Some(values) => {
+            let quoted: Vec<String> = values.iter().map(|v| format!("'{}'", v.replace('\'', "''"))).collect();
+            out.push_str(&format!(" IN({})", quoted.join(",")));
+        }
+        None => {
+            for check in table.checks.iter().filter(|c| single(&c.columns)) {
+                out.push_str(&format!(" {}", check.definition));
+            }
+        }
+    }
+
+    out
+}
+
+/// Shortens the long spellings `format_type` uses.
+fn short_type(data_type: &str) -> String {
+    data_type
+        .replace("character varying", "varchar")
+        .replace("timestamp without time zone", "timestamp")
+        .replace("timestamp with time zone", "timestamptz")
+        .replace("time without time zone", "time")
+        .replace("time with time zone", "timetz")
+        .replace("double precision", "float8")
+        .replace("character", "char")
+}
@@ -0,0 +1,347 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool, Row};
+use tokio::sync::Mutex;
+
+use super::super::AppState;
+use crate::config::SchemaConfig;
+use crate::error::AppError;
+use crate::extract::AppQuery;
+use crate::routes::sql::begin_with_timeout;
+
+#[derive(Serialize)]
+pub struct SchemaStats {
+    pub generated_at: DateTime<Utc>,
+    pub tables: Vec<TableStats>,
+}
+
+#[derive(Serialize)]
+pub struct TableStats {
+    pub schema: String,
+    pub name: String,
+    /// Planner's row estimate; `None` if the table was never analyzed.
+    pub row_estimate: Option<f64>,
+    /// Whether query-based figures were computed on a `TABLESAMPLE`.
+    pub sampled: bool,
+    pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Serialize)]
+pub struct ColumnStats {
+    pub name: String,
+    pub data_type: String,
+    /// Where the distinct count, null fraction and top values came from:
+    /// `pg_stats`, `sample` or `full_scan`.
+    pub source: &'static str,
+    pub distinct_count: Option<f64>,
+    pub null_fraction: Option<f64>,
+    /// Smallest and largest value of numeric and date/time columns, as text.
+    /// Left out for sampled tables, where finding them means a full scan.
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Most frequent values of low-cardinality text, enum and boolean columns.
+    pub top_values: Option<Vec<TopValue>>,
+}
+
+#[derive(Serialize)]
+pub struct TopValue {
+    pub value: Option<String>,
+    pub frequency: f64,
+}
+
+/// Computed statistics, rebuilt at most once per refresh interval. Requests
+/// that arrive during a rebuild wait for it instead of starting their own.
+#[derive(Clone)]
+pub struct StatsCache {
+    config: SchemaConfig,
+    cached: Arc<Mutex<Option<(Instant, serde_json::Value)>>>,
+}
+
+impl StatsCache {
+    pub fn new(config: SchemaConfig) -> Self {
+        Self {
+            config,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    /// Rebuild now instead of serving cached statistics. Ignored while the
+    /// cached statistics are younger than `stats_min_refresh_secs`.
+    #[serde(default)]
+    refresh: bool,
+}
+
+pub async fn get_stats(
+    State(state): State<AppState>,
+    AppQuery(q): AppQuery<StatsQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let cache = &state.schema_stats;
+    let refresh_after = Duration::from_secs(cache.config.stats_refresh_secs);
+    let min_refresh = Duration::from_secs(cache.config.stats_min_refresh_secs);
+
+    let mut cached = cache.cached.lock().await;
+    if let Some((built_at, stats)) = cached.as_ref() {
+        let age = built_at.elapsed();
+        if age < min_refresh || (!q.refresh && age < refresh_after) {
+            return Ok(Json(stats.clone()));
+        }
+    }
+
+    let stats = serde_json::json!(collect_stats(&state.readonly_pool, &cache.config).await?);
+    *cached = Some((Instant::now(), stats.clone()));
+    Ok(Json(stats))
+}
+
+struct ColumnInfo {
+    name: String,
+    data_type: String,
+    /// `pg_type.typcategory`: N numeric, D date/time, S string, E enum,
+    /// B boolean, ...
+    category: String,
+}
+
+struct TableInfo {
+    schema: String,
+    name: String,
+    reltuples: f64,
+    columns: Vec<ColumnInfo>,
+}
+
+struct PgStatsRow {
+    schema: String,
+    table: String,
+    column: String,
+    null_frac: f64,
+    n_distinct: f64,
+    common_values: Option<Vec<Option<String>>>,
+    common_freqs: Option<Vec<f64>>,
+}
+
+/// Builds statistics for every table the read-only role can select from.
+/// Columns Postgres has statistics for use `pg_stats`; the rest are counted
+/// directly, on a sample for tables above `stats_sample_rows`. Every
+/// statement runs under `stats_timeout_ms`.
+pub async fn collect_stats(pool: &PgPool, config: &SchemaConfig) -> Result<SchemaStats, sqlx::Error> {
+    let (mut tx, cancel) = begin_with_timeout(pool, config.stats_timeout_ms).await?;
+    let stats = collect_stats_on(&mut tx, config).await;
+    cancel.disarm();
+    tx.rollback().await?;
+    stats
+}
+
+async fn collect_stats_on(conn: &mut PgConnection, config: &SchemaConfig) -> Result<SchemaStats, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String, f64, String, String, String)>(
+        "SELECT n.nspname::text, c.relname::text, c.reltuples::float8, a.attname::text, \
+                format_type(a.atttypid, a.atttypmod), t.typcategory::text \
+         FROM pg_catalog.pg_class c \
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         JOIN pg_catalog.pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped \
+         JOIN pg_catalog.pg_type t ON t.oid = a.atttypid \
+         WHERE c.relkind IN ('r', 'p', 'm') \
+           AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+           AND n.nspname NOT LIKE 'pg_toast%' \
+           AND has_table_privilege(c.oid, 'SELECT') \
+         ORDER BY n.nspname, c.relname, a.attnum",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut tables: Vec<TableInfo> = vec![];
+    for (schema, name, reltuples, column, data_type, category) in rows {
+        let same_table = tables.last().is_some_and(|t| t.schema == schema && t.name == name);
+        if !same_table {
+            tables.push(TableInfo {
+                schema,
+                name,
+                reltuples,
+                columns: vec![],
+            });
+        }
+        if let Some(table) = tables.last_mut() {
+            table.columns.push(ColumnInfo {
+                name: column,
+                data_type,
+                category,
+            });
+        }
+    }
+
+    let pg_stats: Vec<PgStatsRow> = sqlx::query_as::<_, (String, String, String, f64, f64, Option<Vec<Option<String>>>, Option<Vec<f64>>)>(
+        "SELECT schemaname::text, tablename::text, attname::text, null_frac::float8, \
+                n_distinct::float8, most_common_vals::text::text[], most_common_freqs::float8[] \
+         FROM pg_catalog.pg_stats \
+         WHERE NOT inherited",
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|(schema, table, column, null_frac, n_distinct, common_values, common_freqs)| PgStatsRow {
+        schema,
+        table,
+        column,
+        null_frac,
+        n_distinct,
+        common_values,
+        common_freqs,
+    })
+    .collect();
+
+    let mut result = vec![];
+    for table in &tables {
+        result.push(table_stats(conn, config, table, &pg_stats).await?);
+    }
+
+    Ok(SchemaStats {
+        generated_at: Utc::now(),
+        tables: result,
+    })
+}
+
+async fn table_stats(
+    conn: &mut PgConnection,
+    config: &SchemaConfig,
+    table: &TableInfo,
+    pg_stats: &[PgStatsRow],
+) -> Result<TableStats, sqlx::Error> {
+    let row_estimate = (table.reltuples >= 0.0).then_some(table.reltuples);
+    let sampled = row_estimate.is_some_and(|rows| rows > config.stats_sample_rows);
+    let from = if sampled {
+        let percent = (100.0 * config.stats_sample_rows / table.reltuples).clamp(0.01, 100.0);
+        format!("{}.{} TABLESAMPLE SYSTEM ({})", ident(&table.schema), ident(&table.name), percent)
+    } else {
+        format!("{}.{}", ident(&table.schema), ident(&table.name))
+    };
+    let scanned_source = if sampled { "sample" } else { "full_scan" };
+
+    let stats_for = |column: &ColumnInfo| {
+        pg_stats
+            .iter()
+            .find(|s| s.schema == table.schema && s.table == table.name && s.column == column.name)
+    };
+    let countable = |c: &ColumnInfo| matches!(c.category.as_str(), "N" | "D" | "S" | "E" | "B");
+    // Min and max over a sample aren't the table's, and the exact ones would
+    // cost the full scan sampling avoids.
+    let ordered = |c: &ColumnInfo| !sampled && matches!(c.category.as_str(), "N" | "D");
+    let listable = |c: &ColumnInfo| matches!(c.category.as_str(), "S" | "E" | "B");
+
+    // One pass over the table (or sample) for row count, min/max and, where
+    // pg_stats has nothing, null and distinct counts.
+    let mut select = vec!["count(*)::float8".to_string()];
+    for column in &table.columns {
+        let col = ident(&column.name);
+        if stats_for(column).is_none() && countable(column) {
+            select.push(format!("count({})::float8", col));
+            select.push(format!("count(DISTINCT {})::float8", col));
+        }
+        if ordered(column) {
+            select.push(format!("min({})::text", col));
+            select.push(format!("max({})::text", col));
+        }
+    }
+    let row = sqlx::query(&format!("SELECT {} FROM {}", select.join(", "), from))
+        .fetch_one(&mut *conn)
+        .await?;
+    let total: f64 = row.try_get(0)?;
+
+    let mut idx = 1;
+    let mut columns = vec![];
+    for column in &table.columns {
+        let mut stats = ColumnStats {
+            name: column.name.clone(),
+            data_type: column.data_type.clone(),
+            source: scanned_source,
+            distinct_count: None,
+            null_fraction: None,
+            min: None,
+            max: None,
+            top_values: None,
+        };
+
+        match stats_for(column) {
+            Some(s) => {
+                stats.source = "pg_stats";
+                stats.null_fraction = Some(s.null_frac);
+                stats.distinct_count = Some(if s.n_distinct >= 0.0 {
+                    s.n_distinct
+                } else {
+                    (-s.n_distinct * table.reltuples).round()
+                });
+                if let (Some(values), Some(freqs)) = (&s.common_values, &s.common_freqs) {
+                    stats.top_values = Some(
+                        values
+                            .iter()
+                            .zip(freqs)
+                            .take(config.stats_top_values)
+                            .map(|(value, &frequency)| TopValue {
+                                value: value.clone(),
+                                frequency,
+                            })
+                            .collect(),
+                    );
+                }
+            }
+            None if countable(column) => {
+                let non_null: f64 = row.try_get(idx)?;
+                let distinct: f64 = row.try_get(idx + 1)?;
+                idx += 2;
+                stats.null_fraction = (total > 0.0).then(|| (total - non_null) / total);
+                stats.distinct_count = Some(distinct);
+            }
+            None => {}
+        }
+
+        if ordered(column) {
+            stats.min = row.try_get(idx)?;
+            stats.max = row.try_get(idx + 1)?;
+            idx += 2;
+        }
+
+        let low_cardinality = stats
+            .distinct_count
+            .is_some_and(|d| d <= config.stats_max_distinct_for_top);
+        if !listable(column) || !low_cardinality {
+            stats.top_values = None;
+        } else if stats.top_values.is_none() && total > 0.0 {
+            let counts = sqlx::query_as::<_, (Option<String>, f64)>(&format!(
+                "SELECT {col}::text, count(*)::float8 FROM {from} \
+                 GROUP BY 1 ORDER BY 2 DESC, 1 LIMIT {limit}",
+                col = ident(&column.name),
+                from = from,
+                limit = config.stats_top_values,
+            ))
+            .fetch_all(&mut *conn)
+            .await?;
+            stats.top_values = Some(
+                counts
+                    .into_iter()
+                    .map(|(value, count)| TopValue {
+                        value,
+                        frequency: count / total,
+                    })
+                    .collect(),
+            );
+        }
+
+        columns.push(stats);
+    }
+
+    Ok(TableStats {
+        schema: table.schema.clone(),
+        name: table.name.clone(),
+        row_estimate,
+        sampled,
+        columns,
+    })
+}
+
+fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -0,0 +1,298 @@
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use tokio::sync::mpsc;
+
+use super::timeout::is_timeout;
+use super::SqlRequest;
+use crate::error::AppError;
+use crate::extract::AppQuery;
+
+/// Entries waiting to be written; once full, new entries are dropped (and
+/// logged) rather than slowing down queries.
+const QUEUE_CAPACITY: usize = 1024;
+const MAX_CALLER_LEN: usize = 255;
+
+#[derive(Clone, Copy)]
+pub enum Outcome {
+    Success,
+    /// Refused before running: validation, parameters, paging or cost guard.
+    Rejected,
+    Failed,
+    Timeout,
+    /// The client went away while rows were still streaming.
+    Cancelled,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Rejected => "rejected",
+            Outcome::Failed => "failed",
+            Outcome::Timeout => "timeout",
+            Outcome::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Writes one row to `sql_audit_log` per `/api/sql` call. Entries are handed
+/// to a background task over a channel so the response never waits on the
+/// insert.
+#[derive(Clone)]
+pub struct AuditLog {
+    sender: mpsc::Sender<AuditEntry>,
+}
+
+struct AuditEntry {
+    caller: String,
+    query: String,
+    query_hash: String,
+    params: serde_json::Value,
+    mode: &'static str,
+    outcome: Outcome,
+    error_code: Option<String>,
+    error: Option<String>,
+    row_count: Option<i64>,
+    duration_ms: f64,
+}
+
+impl AuditLog {
+    /// Starts the writer task on `pool`, which must be able to insert into
+    /// `sql_audit_log`.
+    pub fn spawn(pool: PgPool) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<AuditEntry>(QUEUE_CAPACITY);
+
+        tokio::spawn(async move {
+            while let Some(entry) = receiver.recv().await {
+                if let Err(e) = insert(&pool, &entry).await {
+                    tracing::warn!("Failed to write SQL audit entry for {}: {}", entry.query_hash, e);
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Opens an entry for `req`; the clock for `duration_ms` starts now.
+    pub fn start(&self, caller: String, req: &SqlRequest, mode: &'static str) -> PendingAudit {
+        let query = req.query.trim().to_string();
+        PendingAudit {
+            log: self.clone(),
+            caller,
+            query_hash: hex::encode(Sha256::digest(query.as_bytes())),
+            query,
+            params: serde_json::Value::Array(req.params.clone()),
+            mode,
+            started: Instant::now(),
+            finished: false,
+        }
+    }
+}
+
+/// An `/api/sql` call that hasn't finished yet. Finishing it queues the
+/// audit row; dropping it unfinished (the client disconnected and the
+/// handler was dropped) records the call as cancelled.
+pub struct PendingAudit {
+    log: AuditLog,
+    caller: String,
+    query: String,
+    query_hash: String,
+    params: serde_json::Value,
+    mode: &'static str,
+    started: Instant,
+    finished: bool,
+}
+
+impl PendingAudit {
+    pub fn finish(mut self, outcome: Outcome, row_count: Option<i64>, error: Option<(&str, String)>) {
+        self.record(outcome, row_count, error);
+    }
+
+    fn record(&mut self, outcome: Outcome, row_count: Option<i64>, error: Option<(&str, String)>) {
+        self.finished = true;
+        let (error_code, error) = match error {
+            Some((code, message)) => (Some(code.to_string()), Some(message)),
+            None => (None, None),
+        };
+
+        let entry = AuditEntry {
+            caller: std::mem::take(&mut self.caller),
+            query: std::mem::take(&mut self.query),
+            query_hash: std::mem::take(&mut self.query_hash),
+            params: self.params.take(),
+            mode: self.mode,
+            outcome,
+            error_code,
+            error,
+            row_count,
+            duration_ms: self.started.elapsed().as_secs_f64() * 1000.0,
+        };
+
+        if let Err(e) = self.log.sender.try_send(entry) {
+            tracing::warn!("Dropped SQL audit entry: {}", e);
+        }
+    }
+
+    /// Records a statement that failed while running.
+    pub fn failed(self, e: &sqlx::Error) {
+        if is_timeout(e) {
+            self.finish(Outcome::Timeout, None, Some(("query_timeout", e.to_string())));
+        } else {
+            self.finish(Outcome::Failed, None, Some(("query_failed", e.to_string())));
+        }
+    }
+
+    /// Records a buffered JSON response, reading the outcome from its error
+    /// `code` and the row count from `row_count`, then returns it.
+    pub fn respond(self, (status, body): (StatusCode, Json<serde_json::Value>)) -> Response {
+        let row_count = body["row_count"].as_i64();
+
+        if status.is_success() {
+            self.finish(Outcome::Success, row_count, None);
+        } else {
+            let code = body["code"].as_str().unwrap_or_default();
+            let outcome = match code {
+                "query_timeout" => Outcome::Timeout,
+                "query_failed" | "unavailable" | "internal_error" => Outcome::Failed,
+                _ => Outcome::Rejected,
+            };
+            let message = body["error"].as_str().unwrap_or_default().to_string();
+            self.finish(outcome, row_count, Some((code, message)));
+        }
+
+        (status, body).into_response()
+    }
+}
+
+impl Drop for PendingAudit {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.record(
+                Outcome::Cancelled,
+                None,
+                Some(("client_disconnected", "Client disconnected before the response".to_string())),
+            );
+        }
+    }
+}
+
+async fn insert(pool: &PgPool, entry: &AuditEntry) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO sql_audit_log \
+         (caller, query, query_hash, params, mode, outcome, error_code, error, row_count, duration_ms) \
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)",
+    )
+    .bind(&entry.caller)
+    .bind(&entry.query)
+    .bind(&entry.query_hash)
+    .bind(&entry.params)
+    .bind(entry.mode)
+    .bind(entry.outcome.as_str())
+    .bind(&entry.error_code)
+    .bind(&entry.error)
+    .bind(entry.row_count)
+    .bind(entry.duration_ms)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Who is running the query: the `X-Caller` header when the client sends
+/// one, otherwise its address.
+pub fn caller(headers: &HeaderMap, peer: SocketAddr) -> String {
+    match headers.get("x-caller").and_then(|v| v.to_str().ok()).map(str::trim) {
+        Some(name) if !name.is_empty() => name.chars().take(MAX_CALLER_LEN).collect(),
+        _ => peer.ip().to_string(),
+    }
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub executed_at: DateTime<Utc>,
+    pub caller: String,
+    pub query: String,
+    pub query_hash: String,
+    pub params: serde_json::Value,
+    pub mode: String,
+    pub outcome: String,
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+    pub row_count: Option<i64>,
+    pub duration_ms: f64,
+}
+
+/// Filters for `GET /api/sql/history`; times are RFC 3339.
+#[derive(Deserialize)]
+pub struct HistoryFilter {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    outcome: Option<String>,
+    caller: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Most recent audit entries first, at most 1000 per page.
+pub async fn history(
+    State(pool): State<PgPool>,
+    AppQuery(f): AppQuery<HistoryFilter>,
+) -> Result<Json<Vec<AuditRecord>>, AppError> {
+    let rows = sqlx::query_as::<_, AuditRecord>(
+        "SELECT * FROM sql_audit_log \
+         WHERE ($1::timestamptz IS NULL OR executed_at >= $1) \
+           AND ($2::timestamptz IS NULL OR executed_at < $2) \
+           AND ($3::text IS NULL OR outcome = $3) \
+           AND ($4::text IS NULL OR caller = $4) \
+         ORDER BY executed_at DESC, id DESC \
+         LIMIT $5 OFFSET $6",
+    )
+    .bind(f.from)
+    .bind(f.to)
+    .bind(&f.outcome)
+    .bind(&f.caller)
+    .bind(f.limit.unwrap_or(100).clamp(1, 1000))
+    .bind(f.offset.unwrap_or(0).max(0))
+    .fetch_all(&pool)
+    .await?;
+    Ok(Json(rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(log: &AuditLog) -> PendingAudit {
+        let req: SqlRequest = serde_json::from_value(serde_json::json!({ "query": "SELECT 1" })).unwrap();
+        log.start("tester".to_string(), &req, "json")
+    }
+
+    #[test]
+    fn dropped_calls_are_recorded_as_cancelled() {
+        let (sender, mut receiver) = mpsc::channel(4);
+        let log = AuditLog { sender };
+
+        pending(&log).finish(Outcome::Success, Some(1), None);
+        let entry = receiver.try_recv().unwrap();
+        assert_eq!(entry.outcome.as_str(), "success");
+        assert_eq!(entry.query, "SELECT 1");
+        assert!(receiver.try_recv().is_err());
+
+        drop(pending(&log));
+        let entry = receiver.try_recv().unwrap();
+        assert_eq!(entry.outcome.as_str(), "cancelled");
+        assert_eq!(entry.error_code.as_deref(), Some("client_disconnected"));
+        assert_eq!(entry.caller, "tester");
+        assert!(entry.duration_ms >= 0.0);
+        assert!(receiver.try_recv().is_err());
+    }
+}
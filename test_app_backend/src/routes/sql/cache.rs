@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sqlparser::ast::visit_relations;
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+/// In-process cache of buffered `/api/sql` responses. Entries expire after
+/// `ttl`, the least recently used ones are evicted to stay within
+/// `max_bytes`, and writes to a table drop every entry that read from it.
+#[derive(Clone)]
+pub struct ResultCache {
+    ttl: Duration,
+    max_bytes: usize,
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<String, CacheEntry>,
+    bytes: usize,
+    /// Bumped by every invalidation; see `ResultCache::generation`.
+    generation: u64,
+    /// Generation at which each table was last written.
+    written_at: HashMap<String, u64>,
+}
+
+struct CacheEntry {
+    body: serde_json::Value,
+    tables: Vec<String>,
+    size: usize,
+    expires_at: Instant,
+    last_used: Instant,
+}
+
+/// What a request is cached under, plus the tables its query reads.
+pub struct CacheKey {
+    key: String,
+    tables: Vec<String>,
+}
+
+impl CacheKey {
+    /// Keys on the query as re-printed by the parser, so whitespace and
+    /// keyword case don't matter, together with the params and row limit.
+    /// Unquoted identifiers are folded to lowercase first, as Postgres does.
+    /// Returns `None` for queries that don't parse; they can't run anyway.
+    pub fn new(query: &str, params: &[serde_json::Value], max_rows: Option<usize>) -> Option<Self> {
+        let statements = Parser::parse_sql(&PostgreSqlDialect {}, &fold_identifiers(query)?).ok()?;
+
+        let mut tables = vec![];
+        let _ = visit_relations(&statements, |relation| {
+            if let Some(name) = relation.0.last() {
+                tables.push(name.value.to_lowercase());
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        tables.sort();
+        tables.dedup();
+
+        let normalized: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+        let key = serde_json::json!([normalized, params, max_rows]).to_string();
+        Some(Self { key, tables })
+    }
+}
+
+/// Lowercases every unquoted word, leaving quoted identifiers and string
+/// literals alone.
+fn fold_identifiers(query: &str) -> Option<String> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, query).tokenize().ok()?;
+    Some(
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                Token::Word(mut word) if word.quote_style.is_none() => {
+                    word.value = word.value.to_lowercase();
+                    Token::Word(word).to_string()
+                }
+                token => token.to_string(),
+            })
+            .collect(),
+    )
+}
+
+impl ResultCache {
+    /// A zero `ttl` turns the cache off.
+    pub fn new(ttl: Duration, max_bytes: usize) -> Self {
+        Self {
+            ttl,
+            max_bytes,
+            inner: Arc::new(Mutex::new(CacheInner::default())),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Returns the cached body and how long it stays fresh.
+    pub fn get(&self, key: &CacheKey) -> Option<(serde_json::Value, Duration)> {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+
+        let entry = inner.entries.get_mut(&key.key)?;
+        if entry.expires_at <= now {
+            let size = entry.size;
+            inner.entries.remove(&key.key);
+            inner.bytes -= size;
+            return None;
+        }
+
+        entry.last_used = now;
+        Some((entry.body.clone(), entry.expires_at - now))
+    }
+
+    /// Take this before running a query and pass it to `insert`, so a result
+    /// read before a concurrent write isn't cached after that write's
+    /// invalidation has already happened.
+    pub fn generation(&self) -> u64 {
+        self.inner.lock().unwrap().generation
+    }
+
+    pub fn insert(&self, key: CacheKey, body: serde_json::Value, generation: u64) {
+        let size = key.key.len() + body.to_string().len();
+        if !self.enabled() || size > self.max_bytes {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+
+        let stale = key
+            .tables
+            .iter()
+            .any(|t| inner.written_at.get(t).is_some_and(|&g| g > generation));
+        if stale {
+            return;
+        }
+
+        inner.entries.retain(|_, e| e.expires_at > now);
+        inner.bytes = inner.entries.values().map(|e| e.size).sum();
+
+        while inner.bytes + size > self.max_bytes {
+            let Some(oldest) = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+            else {
+                break;
+            };
+            if let Some(evicted) = inner.entries.remove(&oldest) {
+                inner.bytes -= evicted.size;
+            }
+        }
+
+        let entry = CacheEntry {
+            body,
+            tables: key.tables,
+            size,
+            expires_at: now + self.ttl,
+            last_used: now,
+        };
+        if let Some(replaced) = inner.entries.insert(key.key, entry) {
+            inner.bytes -= replaced.size;
+        }
+        inner.bytes += size;
+    }
+
+    /// Drops every entry that reads any of `tables`.
+    pub fn invalidate(&self, tables: &[&str]) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.generation += 1;
+        let generation = inner.generation;
+        for table in tables {
+            inner.written_at.insert(table.to_string(), generation);
+        }
+
+        inner
+            .entries
+            .retain(|_, e| !e.tables.iter().any(|t| tables.contains(&t.as_str())));
+        inner.bytes = inner.entries.values().map(|e| e.size).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(query: &str) -> String {
+        CacheKey::new(query, &[], None).unwrap().key
+    }
+
+    #[test]
+    fn keys_ignore_case_and_whitespace() {
+        assert_eq!(key("select count(*) from VEHICLES"), key("SELECT count(*)\n  FROM vehicles"));
+        assert_eq!(key("SELECT Make FROM Vehicles WHERE Id = 1"), key("select make from vehicles where id = 1"));
+        assert_eq!(CacheKey::new("SELECT * FROM Vehicles", &[], None).unwrap().tables, ["vehicles"]);
+    }
+
+    #[test]
+    fn keys_keep_quoted_identifiers_and_literals() {
+        assert_ne!(key(r#"SELECT * FROM "Vehicles""#), key("SELECT * FROM vehicles"));
+        assert_ne!(key("SELECT * FROM vehicles WHERE make = 'BMW'"), key("SELECT * FROM vehicles WHERE make = 'bmw'"));
+        assert_ne!(key("SELECT $$Ab$$"), key("SELECT $$ab$$"));
+    }
+}
@@ -0,0 +1,98 @@
+use serde::Serialize;
+use sqlx::postgres::types::Oid;
+use sqlx::{Column, Describe, PgPool, Postgres, TypeInfo};
+
+/// Describes one result column so clients don't have to guess how to render
+/// or chart it.
+#[derive(Serialize)]
+pub struct ColumnMeta {
+    pub name: String,
+    /// Postgres type name as reported by the server, e.g. `int4`, `numeric`.
+    pub pg_type: String,
+    /// One of integer, decimal, float, date, timestamp, text, boolean, json.
+    pub logical_type: &'static str,
+    /// Whether the column can be null, when Postgres lets us tell.
+    pub nullable: Option<bool>,
+    /// Table and column the value was read from, if it isn't an expression.
+    pub source_table: Option<String>,
+    pub source_column: Option<String>,
+}
+
+/// Maps a Postgres type name to the logical type clients should use. Decimal
+/// values are serialized as strings to keep their precision.
+pub fn logical_type(pg_type: &str) -> &'static str {
+    if pg_type.ends_with("[]") {
+        return "json";
+    }
+    match pg_type {
+        "INT2" | "INT4" | "INT8" | "OID" => "integer",
+        "NUMERIC" | "MONEY" => "decimal",
+        "FLOAT4" | "FLOAT8" => "float",
+        "DATE" => "date",
+        "TIMESTAMP" | "TIMESTAMPTZ" => "timestamp",
+        "BOOL" => "boolean",
+        "JSON" | "JSONB" => "json",
+        _ => "text",
+    }
+}
+
+struct SourceColumn {
+    relation_id: Oid,
+    attnum: i16,
+    table: String,
+    column: String,
+}
+
+/// Builds column metadata from a statement description, looking up the
+/// originating table and column names in the catalog for columns Postgres
+/// traced back to a table. Catalog errors only drop the source details.
+pub async fn describe_columns(pool: &PgPool, described: &Describe<Postgres>) -> Vec<ColumnMeta> {
+    let columns = described.columns();
+    let relation_ids: Vec<Oid> = columns.iter().filter_map(|c| c.relation_id()).collect();
+
+    let sources: Vec<SourceColumn> = if relation_ids.is_empty() {
+        vec![]
+    } else {
+        sqlx::query_as::<_, (Oid, i16, String, String)>(
+            "SELECT a.attrelid, a.attnum, c.relname::text, a.attname::text \
+             FROM pg_catalog.pg_attribute a \
+             JOIN pg_catalog.pg_class c ON c.oid = a.attrelid \
+             WHERE a.attrelid = ANY($1) AND a.attnum > 0",
+        )
+        .bind(&relation_ids)
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(relation_id, attnum, table, column)| SourceColumn {
+            relation_id,
+            attnum,
+            table,
+            column,
+        })
+        .collect()
+    };
+
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            let type_name = col.type_info().name();
+            let source = match (col.relation_id(), col.relation_attribute_no()) {
+                (Some(rel), Some(attnum)) => sources
+                    .iter()
+                    .find(|s| s.relation_id == rel && s.attnum == attnum),
+                _ => None,
+            };
+
+            ColumnMeta {
+                name: col.name().to_string(),
+                pg_type: type_name.to_lowercase(),
+                logical_type: logical_type(type_name),
+                nullable: described.nullable(i),
+                source_table: source.map(|s| s.table.clone()),
+                source_column: source.map(|s| s.column.clone()),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Position of the next page of a paged `/api/sql` query. Pages are produced
+/// by re-running the query with `LIMIT`/`OFFSET`, so callers should include
+/// an `ORDER BY` for pages to be stable.
+#[derive(Clone)]
+pub struct PageCursor {
+    pub query: String,
+    pub params: Vec<serde_json::Value>,
+    pub offset: usize,
+    pub page_size: usize,
+    pub total_rows: Option<i64>,
+    expires_at: Instant,
+}
+
+/// Server-side registry of continuation tokens. Tokens are random and only
+/// valid for the exact query text and parameters that produced them.
+#[derive(Clone)]
+pub struct CursorStore {
+    ttl: Duration,
+    cursors: Arc<Mutex<HashMap<String, PageCursor>>>,
+}
+
+pub enum CursorError {
+    Expired,
+    QueryMismatch,
+}
+
+impl CursorStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cursors: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn issue(
+        &self,
+        query: &str,
+        params: &[serde_json::Value],
+        offset: usize,
+        page_size: usize,
+        total_rows: Option<i64>,
+    ) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let now = Instant::now();
+        let cursor = PageCursor {
+            query: query.to_string(),
+            params: params.to_vec(),
+            offset,
+            page_size,
+            total_rows,
+            expires_at: now + self.ttl,
+        };
+
+        let mut cursors = self.cursors.lock().unwrap();
+        cursors.retain(|_, c| c.expires_at > now);
+        cursors.insert(token.clone(), cursor);
+        token
+    }
+
+    /// Looks up `token`, checking it hasn't expired and that it was issued
+    /// for `query` with `params`.
+    pub fn resolve(
+        &self,
+        token: &str,
+        query: &str,
+        params: &[serde_json::Value],
+    ) -> Result<PageCursor, CursorError> {
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = match cursors.get(token) {
+            Some(c) if c.expires_at > Instant::now() => c.clone(),
+            Some(_) => {
+                cursors.remove(token);
+                return Err(CursorError::Expired);
+            }
+            None => return Err(CursorError::Expired),
+        };
+
+        if cursor.query != query || cursor.params != params {
+            return Err(CursorError::QueryMismatch);
+        }
+        Ok(cursor)
+    }
+}
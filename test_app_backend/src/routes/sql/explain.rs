@@ -0,0 +1,65 @@
+use serde::Serialize;
+use sqlx::PgPool;
+
+use super::params::{arguments, ParamValue};
+use super::timeout::begin_with_timeout;
+
+/// Structured result of `EXPLAIN (FORMAT JSON)` for a validated query.
+#[derive(Serialize)]
+pub struct ExplainResponse {
+    /// Planner's estimated cost to return every row, in Postgres cost units.
+    pub total_cost: f64,
+    /// Estimated cost before the first row can be returned.
+    pub startup_cost: f64,
+    /// Rows the planner expects the query to return.
+    pub estimated_rows: f64,
+    /// Only filled in when `analyze` was requested.
+    pub actual_rows: Option<f64>,
+    pub planning_time_ms: Option<f64>,
+    pub execution_time_ms: Option<f64>,
+    /// The full plan tree exactly as Postgres reported it.
+    pub plan: serde_json::Value,
+    pub duration_ms: f64,
+}
+
+/// Plans `query` (and runs it too when `analyze` is set) inside a read-only
+/// transaction bounded by `timeout_ms`. `query` must already have passed
+/// `validate_readonly_sql`: EXPLAIN ANALYZE really executes the statement.
+pub async fn explain_query(
+    pool: &PgPool,
+    query: &str,
+    params: &[ParamValue],
+    analyze: bool,
+    timeout_ms: u64,
+) -> Result<ExplainResponse, sqlx::Error> {
+    let options = if analyze { "ANALYZE, FORMAT JSON" } else { "FORMAT JSON" };
+    let sql = format!("EXPLAIN ({}) {}", options, query);
+
+    let start = std::time::Instant::now();
+    let args = arguments(params)?;
+    let (mut tx, cancel) = begin_with_timeout(pool, timeout_ms).await?;
+    let output = sqlx::query_scalar_with::<_, serde_json::Value, _>(&sql, args)
+        .fetch_one(&mut *tx)
+        .await;
+    cancel.disarm();
+    tx.rollback().await?;
+    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    // EXPLAIN (FORMAT JSON) returns a one-element array:
+    // [{"Plan": {...}, "Planning Time": ..., "Execution Time": ...}]
+    let output = output?;
+    let root = output.get(0).cloned().unwrap_or_default();
+    let plan = root.get("Plan").cloned().unwrap_or_default();
+    let number = |value: &serde_json::Value, key: &str| value.get(key).and_then(|v| v.as_f64());
+
+    Ok(ExplainResponse {
+        total_cost: number(&plan, "Total Cost").unwrap_or_default(),
+        startup_cost: number(&plan, "Startup Cost").unwrap_or_default(),
+        estimated_rows: number(&plan, "Plan Rows").unwrap_or_default(),
+        actual_rows: number(&plan, "Actual Rows"),
+        planning_time_ms: number(&root, "Planning Time"),
+        execution_time_ms: number(&root, "Execution Time"),
+        plan,
+        duration_ms,
+    })
+}
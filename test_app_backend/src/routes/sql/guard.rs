@@ -0,0 +1,207 @@
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+
+use super::explain::explain_query;
+use super::params::ParamValue;
+use crate::config::SqlConfig;
+
+/// What the planner expects a query to cost, plus the plan shapes that
+/// usually explain an expensive query.
+#[derive(Serialize)]
+pub struct PlanSummary {
+    pub node_type: String,
+    pub total_cost: f64,
+    pub estimated_rows: f64,
+    /// Tables read in full whose size is at or above `SQL_GUARD_LARGE_TABLE_ROWS`.
+    pub large_seq_scans: Vec<SeqScan>,
+    /// Joins the planner has no condition for, so every row of one side
+    /// pairs with every row of the other.
+    pub cartesian_products: Vec<CartesianProduct>,
+}
+
+#[derive(Serialize)]
+pub struct SeqScan {
+    pub table: String,
+    pub table_rows: f64,
+}
+
+#[derive(Serialize)]
+pub struct CartesianProduct {
+    pub outer: Vec<String>,
+    pub inner: Vec<String>,
+    pub estimated_rows: f64,
+}
+
+/// Returned when a query's plan is over the configured limits. `reasons` is
+/// written so it can be handed back to whoever generated the SQL.
+#[derive(Serialize)]
+pub struct CostRejection {
+    pub reasons: Vec<String>,
+    pub plan: PlanSummary,
+}
+
+/// Plans `query` without running it and rejects it when the estimated total
+/// cost or row count is over the limits in `config`. `query` must already
+/// have passed `validate_readonly_sql`.
+pub async fn check_cost(
+    pool: &PgPool,
+    query: &str,
+    params: &[ParamValue],
+    config: &SqlConfig,
+    timeout_ms: u64,
+) -> Result<Option<CostRejection>, sqlx::Error> {
+    let explained = explain_query(pool, query, params, false, timeout_ms).await?;
+
+    let over_cost = explained.total_cost > config.guard_max_cost;
+    let over_rows = explained.estimated_rows > config.guard_max_rows;
+    if !over_cost && !over_rows {
+        return Ok(None);
+    }
+
+    let mut scans = vec![];
+    let mut cartesian_products = vec![];
+    walk_plan(&explained.plan, &mut scans, &mut cartesian_products);
+    let large_seq_scans = large_tables(pool, scans, config.guard_large_table_rows).await;
+
+    let mut reasons = vec![];
+    if over_cost {
+        reasons.push(format!(
+            "Estimated cost {:.0} exceeds the limit of {:.0}",
+            explained.total_cost, config.guard_max_cost
+        ));
+    }
+    if over_rows {
+        reasons.push(format!(
+            "Estimated {:.0} result rows exceeds the limit of {:.0}; aggregate or filter the result",
+            explained.estimated_rows, config.guard_max_rows
+        ));
+    }
+    for scan in &large_seq_scans {
+        reasons.push(format!(
+            "Sequential scan over {} (~{:.0} rows); filter on an indexed column or aggregate first",
+            scan.table, scan.table_rows
+        ));
+    }
+    for product in &cartesian_products {
+        reasons.push(format!(
+            "Cartesian product between {} and {} (~{:.0} rows); add a join condition",
+            product.outer.join(", "),
+            product.inner.join(", "),
+            product.estimated_rows
+        ));
+    }
+
+    let node_type = explained.plan["Node Type"].as_str().unwrap_or_default().to_string();
+    Ok(Some(CostRejection {
+        reasons,
+        plan: PlanSummary {
+            node_type,
+            total_cost: explained.total_cost,
+            estimated_rows: explained.estimated_rows,
+            large_seq_scans,
+            cartesian_products,
+        },
+    }))
+}
+
+/// Collects every sequential scan (table name and the planner's row estimate
+/// for it) and every nested loop joining two multi-row inputs without a join
+/// condition.
+fn walk_plan(node: &Value, scans: &mut Vec<SeqScan>, products: &mut Vec<CartesianProduct>) {
+    let rows = |n: &Value| n["Plan Rows"].as_f64().unwrap_or_default();
+
+    if node["Node Type"] == "Seq Scan" {
+        if let Some(table) = node["Relation Name"].as_str() {
+            scans.push(SeqScan {
+                table: table.to_string(),
+                table_rows: rows(node),
+            });
+        }
+    }
+
+    let children = node["Plans"].as_array().map(Vec::as_slice).unwrap_or_default();
+
+    // A parameterized inner side (an index lookup on the outer row's value)
+    // is a normal join; without one, and without a join filter, the loop
+    // pairs every outer row with every inner row.
+    if node["Node Type"] == "Nested Loop" && node.get("Join Filter").is_none() {
+        let side = |relationship: &str| {
+            children
+                .iter()
+                .find(|c| c["Parent Relationship"] == relationship)
+        };
+        if let (Some(outer), Some(inner)) = (side("Outer"), side("Inner")) {
+            if rows(outer) > 1.0 && rows(inner) > 1.0 && !has_index_condition(inner) {
+                products.push(CartesianProduct {
+                    outer: relations(outer),
+                    inner: relations(inner),
+                    estimated_rows: rows(node),
+                });
+            }
+        }
+    }
+
+    for child in children {
+        walk_plan(child, scans, products);
+    }
+}
+
+fn has_index_condition(node: &Value) -> bool {
+    node.get("Index Cond").is_some()
+        || node.get("Recheck Cond").is_some()
+        || node["Plans"]
+            .as_array()
+            .is_some_and(|plans| plans.iter().any(has_index_condition))
+}
+
+/// Names of the tables read under `node`, written `table alias` when aliased.
+fn relations(node: &Value) -> Vec<String> {
+    let mut names = vec![];
+    if let Some(table) = node["Relation Name"].as_str() {
+        match node["Alias"].as_str() {
+            Some(alias) if alias != table => names.push(format!("{} {}", table, alias)),
+            _ => names.push(table.to_string()),
+        }
+    }
+    for child in node["Plans"].as_array().into_iter().flatten() {
+        names.extend(relations(child));
+    }
+    names
+}
+
+/// Keeps the scans over tables with at least `threshold` rows, sizing each
+/// table from the catalog's row estimate. A table that was never analyzed
+/// falls back to the scan's own estimate.
+async fn large_tables(pool: &PgPool, scans: Vec<SeqScan>, threshold: f64) -> Vec<SeqScan> {
+    if scans.is_empty() {
+        return scans;
+    }
+
+    let names: Vec<String> = scans.iter().map(|s| s.table.clone()).collect();
+    let sizes: Vec<(String, f64)> = sqlx::query_as(
+        "SELECT c.relname::text, c.reltuples::float8 \
+         FROM pg_catalog.pg_class c \
+         WHERE c.oid = ANY(SELECT to_regclass(quote_ident(n)) FROM unnest($1::text[]) AS n)",
+    )
+    .bind(&names)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let mut large: Vec<SeqScan> = vec![];
+    for scan in scans {
+        let catalog_rows = sizes
+            .iter()
+            .find(|(name, _)| *name == scan.table)
+            .map_or(0.0, |(_, rows)| *rows);
+        let table_rows = catalog_rows.max(scan.table_rows);
+        if table_rows >= threshold && !large.iter().any(|s| s.table == scan.table) {
+            large.push(SeqScan {
+                table: scan.table,
+                table_rows,
+            });
+        }
+    }
+    large
+}
@@ -0,0 +1,77 @@
+use sqlparser::ast::{Expr, Offset, OffsetRows, Statement, Value};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::parser::Parser;
+
+/// Rewrites an already validated query so Postgres never produces more than
+/// `limit` rows. A missing or larger literal `LIMIT` is replaced in place;
+/// anything the rewrite can't reason about (FETCH FIRST, expression limits)
+/// is wrapped in an outer `SELECT ... LIMIT`.
+pub fn apply_row_limit(query: &str, limit: usize) -> Result<String, String> {
+    let mut statements = Parser::parse_sql(&PostgreSqlDialect {}, query)
+        .map_err(|e| format!("Could not parse query: {}", e))?;
+
+    let Some(Statement::Query(q)) = statements.first_mut() else {
+        return Err("Only read-only queries can be limited".to_string());
+    };
+
+    let limit_expr = Expr::Value(Value::Number(limit.to_string(), false));
+
+    if q.fetch.is_none() {
+        match &q.limit {
+            None => {
+                q.limit = Some(limit_expr);
+                return Ok(q.to_string());
+            }
+            Some(Expr::Value(Value::Number(n, _))) => {
+                if n.parse::<usize>().is_ok_and(|n| n > limit) {
+                    q.limit = Some(limit_expr);
+                }
+                return Ok(q.to_string());
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(format!("SELECT * FROM ({}) AS _limited LIMIT {}", q, limit))
+}
+
+/// Like [`apply_row_limit`], but skips the first `offset` rows. Queries that
+/// already carry their own LIMIT, OFFSET or FETCH are wrapped so their
+/// window is applied before ours.
+pub fn apply_page(query: &str, offset: usize, limit: usize) -> Result<String, String> {
+    if offset == 0 {
+        return apply_row_limit(query, limit);
+    }
+
+    let mut statements = Parser::parse_sql(&PostgreSqlDialect {}, query)
+        .map_err(|e| format!("Could not parse query: {}", e))?;
+
+    let Some(Statement::Query(q)) = statements.first_mut() else {
+        return Err("Only read-only queries can be paged".to_string());
+    };
+
+    if q.limit.is_none() && q.offset.is_none() && q.fetch.is_none() {
+        q.limit = Some(Expr::Value(Value::Number(limit.to_string(), false)));
+        q.offset = Some(Offset {
+            value: Expr::Value(Value::Number(offset.to_string(), false)),
+            rows: OffsetRows::None,
+        });
+        return Ok(q.to_string());
+    }
+
+    Ok(format!(
+        "SELECT * FROM ({}) AS _page LIMIT {} OFFSET {}",
+        q, limit, offset
+    ))
+}
+
+/// Wraps a query so that it returns only the number of rows it would produce.
+pub fn count_query(query: &str) -> Result<String, String> {
+    let statements = Parser::parse_sql(&PostgreSqlDialect {}, query)
+        .map_err(|e| format!("Could not parse query: {}", e))?;
+
+    match statements.first() {
+        Some(Statement::Query(q)) => Ok(format!("SELECT COUNT(*) FROM ({}) AS _total", q)),
+        _ => Err("Only read-only queries can be counted".to_string()),
+    }
+}
@@ -0,0 +1,541 @@
+use axum::extract::{ConnectInfo, State};
+use axum::http::header::CACHE_CONTROL;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::Response;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Describe, Executor, PgPool, Postgres};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use super::AppState;
+use crate::error::{AppError, ErrorCode};
+use crate::extract::AppJson;
+
+mod audit;
+mod cache;
+mod columns;
+mod convert;
+mod cursor;
+mod explain;
+mod guard;
+mod limit;
+mod params;
+mod stream;
+mod timeout;
+mod validate;
+
+pub use audit::{history, AuditLog};
+pub use cache::ResultCache;
+pub use cursor::CursorStore;
+pub use params::{arguments, placeholder_types, resolve_params, ParamError};
+pub use stream::StreamFormat;
+pub use timeout::begin_with_timeout;
+pub use validate::validate_readonly_sql;
+
+use audit::{caller, PendingAudit};
+use cache::CacheKey;
+use columns::{describe_columns, ColumnMeta};
+use convert::pg_value_to_json;
+use cursor::CursorError;
+use explain::explain_query;
+use guard::{check_cost, CostRejection};
+use limit::{apply_page, apply_row_limit, count_query};
+use params::ParamValue;
+use stream::stream_query;
+use timeout::is_timeout;
+
+#[derive(Deserialize, Default)]
+pub struct SqlRequest {
+    pub query: String,
+    /// Values bound to `$1..$n` in `query`, converted to the type Postgres
+    /// infers for each placeholder.
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    /// Caller-requested row limit; clamped to the server's `SQL_MAX_ROWS`.
+    pub max_rows: Option<usize>,
+    /// Statement timeout in milliseconds, at least 1; clamped to
+    /// `SQL_MAX_TIMEOUT_MS`.
+    pub timeout_ms: Option<u64>,
+    /// Enables paged mode: return at most this many rows plus a `next_cursor`.
+    pub page_size: Option<usize>,
+    /// Continuation token from a previous paged response for the same query
+    /// and params.
+    pub cursor: Option<String>,
+    /// Return the query plan instead of the rows.
+    #[serde(default)]
+    pub explain: bool,
+    /// With `explain`, also execute the query to report actual rows and timings.
+    #[serde(default)]
+    pub analyze: bool,
+    /// Neither serve this request from the result cache nor store its result.
+    #[serde(default)]
+    pub no_cache: bool,
+}
+
+#[derive(Serialize)]
+pub struct SqlResponse {
+    columns: Vec<String>,
+    /// Type, nullability and origin of each entry in `columns`.
+    column_types: Vec<ColumnMeta>,
+    rows: Vec<Vec<serde_json::Value>>,
+    row_count: usize,
+    /// True when the query produced more rows than the effective limit.
+    truncated: bool,
+    /// Rows the query would have produced without the limit, when counting
+    /// them fits in the count time budget.
+    total_rows: Option<i64>,
+    /// Token for the next page; only set in paged mode when more rows remain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+    duration_ms: f64,
+}
+
+#[derive(Serialize)]
+struct SqlError {
+    error: String,
+    code: ErrorCode,
+    /// Postgres SQLSTATE when the database rejected the query.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sqlstate: Option<String>,
+}
+
+fn sql_error(code: ErrorCode, error: String) -> (StatusCode, Json<serde_json::Value>) {
+    (code.status(), Json(serde_json::json!(SqlError { error, code, sqlstate: None })))
+}
+
+/// Maps a failed statement to a 504 when it hit the statement timeout and
+/// as `query_failed` otherwise.
+fn query_error(e: sqlx::Error, timeout_ms: u64) -> (StatusCode, Json<serde_json::Value>) {
+    if is_timeout(&e) {
+        sql_error(
+            ErrorCode::QueryTimeout,
+            format!("Query cancelled after exceeding the {} ms statement timeout", timeout_ms),
+        )
+    } else {
+        query_failed(e)
+    }
+}
+
+/// A 400 carrying the database's message and SQLSTATE as is, since they
+/// are about the caller's own query. Anything else, such as a pool timeout
+/// or a dropped connection, is the server's problem and is answered like
+/// any other `AppError`, without the driver's message.
+pub fn query_failed(e: sqlx::Error) -> (StatusCode, Json<serde_json::Value>) {
+    match &e {
+        sqlx::Error::Database(db) if !is_timeout(&e) => {
+            let sqlstate = db.code().map(|c| c.into_owned());
+            let code = ErrorCode::QueryFailed;
+            (code.status(), Json(serde_json::json!(SqlError { error: e.to_string(), code, sqlstate })))
+        }
+        _ => AppError::from(e).into_json(),
+    }
+}
+
+/// Binds the request's `params` to the query's placeholders, or explains
+/// which ones can't be bound.
+async fn bind_params(
+    state: &AppState,
+    query: &str,
+    req: &SqlRequest,
+) -> Result<Vec<ParamValue>, (StatusCode, Json<serde_json::Value>)> {
+    match resolve_params(&state.readonly_pool, query, &req.params).await {
+        Ok(params) => Ok(params),
+        Err(ParamError::Count { expected, given }) => Err(sql_error(
+            ErrorCode::InvalidParams,
+            format!("Query has {} placeholder(s) but {} param(s) were given", expected, given),
+        )),
+        Err(ParamError::Invalid(issues)) => Err((
+            ErrorCode::InvalidParams.status(),
+            Json(serde_json::json!({
+                "error": format!("Parameter ${}: {}", issues[0].index, issues[0].error),
+                "code": ErrorCode::InvalidParams,
+                "params": issues,
+            })),
+        )),
+        Err(ParamError::Query(e)) => Err(query_failed(e)),
+    }
+}
+
+/// Plans the query as the caller wrote it, before any row cap is applied,
+/// and turns a plan over the configured limits into a 422 listing why.
+async fn guard_cost(
+    state: &AppState,
+    query: &str,
+    params: &[ParamValue],
+    timeout_ms: u64,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    match check_cost(&state.readonly_pool, query, params, &state.sql, timeout_ms).await {
+        Ok(None) => Ok(()),
+        Ok(Some(CostRejection { reasons, plan })) => Err((
+            ErrorCode::QueryTooExpensive.status(),
+            Json(serde_json::json!({
+                "error": format!("Query rejected before execution: {}", reasons[0]),
+                "code": ErrorCode::QueryTooExpensive,
+                "reasons": reasons,
+                "plan": plan,
+            })),
+        )),
+        Err(e) => Err(query_error(e, timeout_ms)),
+    }
+}
+
+/// `Accept: application/x-ndjson` or `text/csv` streams rows as they are
+/// read; anything else gets the buffered JSON `SqlResponse`. Every call,
+/// including rejected ones, is written to the audit log.
+pub async fn execute(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    AppJson(req): AppJson<SqlRequest>,
+) -> Response {
+    let format = StreamFormat::from_headers(&headers);
+    let mode = match (req.explain, req.analyze, format) {
+        (true, true, _) => "explain_analyze",
+        (true, false, _) => "explain",
+        (false, _, Some(format)) => format.name(),
+        (false, _, None) => "json",
+    };
+    let audit = state.sql_audit.start(caller(&headers, peer), &req, mode);
+
+    if req.explain {
+        return audit.respond(execute_explain(state, req).await);
+    }
+
+    match format {
+        Some(format) => execute_streamed(state, req, format, audit).await,
+        None => execute_cached(state, req, audit).await,
+    }
+}
+
+/// Serves single-page JSON results from the result cache when possible.
+/// Paged requests always run, since each page hands out a fresh cursor.
+async fn execute_cached(state: AppState, req: SqlRequest, audit: PendingAudit) -> Response {
+    let cacheable = state.sql_cache.enabled()
+        && !req.no_cache
+        && req.page_size.is_none()
+        && req.cursor.is_none();
+    let key = if cacheable {
+        CacheKey::new(req.query.trim(), &req.params, req.max_rows)
+    } else {
+        None
+    };
+
+    let Some(key) = key else {
+        let mut response = audit.respond(execute_json(state, req).await);
+        set_cache_headers(&mut response, "BYPASS", None);
+        return response;
+    };
+
+    if let Some((body, fresh_for)) = state.sql_cache.get(&key) {
+        let mut response = audit.respond((StatusCode::OK, Json(body)));
+        set_cache_headers(&mut response, "HIT", Some(fresh_for));
+        return response;
+    }
+
+    let generation = state.sql_cache.generation();
+    let cache = state.sql_cache.clone();
+    let (status, body) = execute_json(state, req).await;
+    if status == StatusCode::OK {
+        cache.insert(key, body.0.clone(), generation);
+    }
+
+    let mut response = audit.respond((status, body));
+    set_cache_headers(&mut response, "MISS", Some(cache.ttl()));
+    response
+}
+
+fn set_cache_headers(response: &mut Response, status: &'static str, max_age: Option<Duration>) {
+    if !response.status().is_success() {
+        return;
+    }
+    let cache_control = match max_age {
+        Some(age) => format!("private, max-age={}", age.as_secs()),
+        None => "no-store".to_string(),
+    };
+    let headers = response.headers_mut();
+    headers.insert(HeaderName::from_static("x-cache"), HeaderValue::from_static(status));
+    if let Ok(value) = HeaderValue::from_str(&cache_control) {
+        headers.insert(CACHE_CONTROL, value);
+    }
+}
+
+async fn execute_streamed(
+    state: AppState,
+    req: SqlRequest,
+    format: StreamFormat,
+    audit: PendingAudit,
+) -> Response {
+    let query = req.query.trim();
+
+    if let Err(reason) = validate_readonly_sql(query) {
+        return audit.respond(sql_error(ErrorCode::ForbiddenStatement, reason));
+    }
+
+    if req.page_size.is_some() || req.cursor.is_some() {
+        return audit.respond(sql_error(
+            ErrorCode::InvalidRequest,
+            "Paging is only available for JSON responses".to_string(),
+        ));
+    }
+
+    let max_rows = req
+        .max_rows
+        .map_or(state.sql.max_rows, |n| n.min(state.sql.max_rows));
+
+    let limited = match apply_row_limit(query, max_rows) {
+        Ok(sql) => sql,
+        Err(reason) => {
+            return audit.respond(sql_error(ErrorCode::ForbiddenStatement, reason));
+        }
+    };
+
+    let params = match bind_params(&state, query, &req).await {
+        Ok(params) => params,
+        Err(rejected) => return audit.respond(rejected),
+    };
+
+    let timeout_ms = match effective_timeout(&state, &req) {
+        Ok(ms) => ms,
+        Err(rejected) => return audit.respond(rejected),
+    };
+    if let Err(rejected) = guard_cost(&state, query, &params, timeout_ms).await {
+        return audit.respond(rejected);
+    }
+
+    stream_query(state.readonly_pool, limited, params, timeout_ms, max_rows, format, audit).await
+}
+
+async fn execute_explain(state: AppState, req: SqlRequest) -> (StatusCode, Json<serde_json::Value>) {
+    let query = req.query.trim();
+
+    if let Err(reason) = validate_readonly_sql(query) {
+        return sql_error(ErrorCode::ForbiddenStatement, reason);
+    }
+
+    let params = match bind_params(&state, query, &req).await {
+        Ok(params) => params,
+        Err(rejected) => return rejected,
+    };
+
+    let timeout_ms = match effective_timeout(&state, &req) {
+        Ok(ms) => ms,
+        Err(rejected) => return rejected,
+    };
+    match explain_query(&state.readonly_pool, query, &params, req.analyze, timeout_ms).await {
+        Ok(plan) => (StatusCode::OK, Json(serde_json::json!(plan))),
+        Err(e) => query_error(e, timeout_ms),
+    }
+}
+
+/// The statement timeout for `req`, capped at the configured maximum.
+/// `statement_timeout = 0` disables the timeout, so 0 is refused.
+fn effective_timeout(state: &AppState, req: &SqlRequest) -> Result<u64, (StatusCode, Json<serde_json::Value>)> {
+    match req.timeout_ms {
+        Some(0) => Err(sql_error(ErrorCode::InvalidRequest, "timeout_ms must be at least 1".to_string())),
+        Some(ms) => Ok(ms.min(state.sql.max_timeout_ms)),
+        None => Ok(state.sql.default_timeout_ms),
+    }
+}
+
+async fn execute_json(state: AppState, req: SqlRequest) -> (StatusCode, Json<serde_json::Value>) {
+    let query = req.query.trim();
+
+    if let Err(reason) = validate_readonly_sql(query) {
+        return sql_error(ErrorCode::ForbiddenStatement, reason);
+    }
+
+    // Paged requests either continue an existing cursor or start a new one at
+    // offset 0; everything else is a single capped result.
+    let (offset, known_total, paged) = match (&req.cursor, req.page_size) {
+        (Some(token), _) => match state.sql_cursors.resolve(token, query, &req.params) {
+            Ok(c) => (c.offset, c.total_rows, Some(c.page_size)),
+            Err(CursorError::Expired) => {
+                return sql_error(
+                    ErrorCode::CursorExpired,
+                    "Cursor is unknown or has expired; rerun the query without a cursor".to_string(),
+                );
+            }
+            Err(CursorError::QueryMismatch) => {
+                return sql_error(
+                    ErrorCode::CursorMismatch,
+                    "Cursor was issued for a different query or params".to_string(),
+                );
+            }
+        },
+        (None, Some(size)) => (0, None, Some(size.max(1))),
+        (None, None) => (0, None, None),
+    };
+
+    let max_rows = paged
+        .or(req.max_rows)
+        .map_or(state.sql.max_rows, |n| n.min(state.sql.max_rows));
+
+    // Ask for one extra row so we can tell whether the result was cut off.
+    let limited = match apply_page(query, offset, max_rows + 1) {
+        Ok(sql) => sql,
+        Err(reason) => return sql_error(ErrorCode::ForbiddenStatement, reason),
+    };
+
+    let params = match bind_params(&state, query, &req).await {
+        Ok(params) => params,
+        Err(rejected) => return rejected,
+    };
+
+    let timeout_ms = match effective_timeout(&state, &req) {
+        Ok(ms) => ms,
+        Err(rejected) => return rejected,
+    };
+    if let Err(rejected) = guard_cost(&state, query, &params, timeout_ms).await {
+        return rejected;
+    }
+
+    let pool: &PgPool = &state.readonly_pool;
+
+    let start = Instant::now();
+    let result = fetch_with_timeout(pool, &limited, &params, timeout_ms).await;
+    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    match result {
+        Ok((described, mut rows)) => {
+            let truncated = rows.len() > max_rows;
+            rows.truncate(max_rows);
+
+            let column_types = describe_columns(pool, &described).await;
+            let columns: Vec<String> = column_types.iter().map(|c| c.name.clone()).collect();
+
+            let json_rows: Vec<Vec<serde_json::Value>> = rows
+                .iter()
+                .map(|row| {
+                    columns
+                        .iter()
+                        .enumerate()
+                        .map(|(i, _)| pg_value_to_json(row, i))
+                        .collect()
+                })
+                .collect();
+
+            let row_count = json_rows.len();
+            let total_rows = match known_total {
+                Some(total) => Some(total),
+                None if truncated => count_total(pool, query, &params, state.sql.count_timeout_ms).await,
+                None => Some((offset + row_count) as i64),
+            };
+
+            let next_cursor = match paged {
+                Some(page_size) if truncated => Some(state.sql_cursors.issue(
+                    query,
+                    &req.params,
+                    offset + row_count,
+                    page_size,
+                    total_rows,
+                )),
+                _ => None,
+            };
+
+            (
+                StatusCode::OK,
+                Json(serde_json::json!(SqlResponse {
+                    columns,
+                    column_types,
+                    rows: json_rows,
+                    row_count,
+                    truncated,
+                    total_rows,
+                    next_cursor,
+                    duration_ms,
+                })),
+            )
+        }
+        Err(e) => query_error(e, timeout_ms),
+    }
+}
+
+/// Runs `sql` in its own read-only transaction bounded by `timeout_ms`.
+/// If the caller disconnects mid-query the future is dropped and the
+/// backend statement is cancelled instead of running to completion.
+///
+/// The statement is described before it runs so column names and types are
+/// known even when it returns no rows; the prepared statement is cached on
+/// the connection and reused by the fetch.
+async fn fetch_with_timeout(
+    pool: &PgPool,
+    sql: &str,
+    params: &[ParamValue],
+    timeout_ms: u64,
+) -> Result<(Describe<Postgres>, Vec<PgRow>), sqlx::Error> {
+    let args = arguments(params)?;
+    let (mut tx, cancel) = begin_with_timeout(pool, timeout_ms).await?;
+    let result = async {
+        let described = (&mut *tx).describe(sql).await?;
+        let rows = sqlx::query_with(sql, args).fetch_all(&mut *tx).await?;
+        Ok((described, rows))
+    }
+    .await;
+    cancel.disarm();
+    tx.rollback().await?;
+    result
+}
+
+/// Counts the rows an unlimited run would return, giving up (and returning
+/// `None`) once the count exceeds `timeout_ms`.
+async fn count_total(
+    pool: &PgPool,
+    query: &str,
+    params: &[ParamValue],
+    timeout_ms: u64,
+) -> Option<i64> {
+    let sql = count_query(query).ok()?;
+    let args = arguments(params).ok()?;
+
+    let (mut tx, cancel) = begin_with_timeout(pool, timeout_ms).await.ok()?;
+    let total = sqlx::query_scalar_with::<_, i64, _>(&sql, args)
+        .fetch_one(&mut *tx)
+        .await
+        .ok();
+    cancel.disarm();
+    let _ = tx.rollback().await;
+
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn infrastructure_errors_are_not_query_failures() {
+        let (status, Json(body)) = query_failed(sqlx::Error::PoolTimedOut);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "unavailable");
+        assert_eq!(body["error"], "Database is busy");
+
+        let (status, Json(body)) = query_failed(sqlx::Error::Protocol("unexpected message".to_string()));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "internal_error");
+        assert!(!body.to_string().contains("unexpected message"));
+    }
+
+    #[tokio::test]
+    async fn database_errors_are_query_failures() {
+        let Some(pool) = test_util::pool().await else {
+            return;
+        };
+
+        let e = sqlx::query("SELECT no_such_column FROM vehicles").execute(&pool).await.unwrap_err();
+        let (status, Json(body)) = query_failed(e);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "query_failed");
+        assert_eq!(body["sqlstate"], "42703");
+        assert!(body["error"].as_str().unwrap().contains("no_such_column"));
+
+        let (mut tx, cancel) = begin_with_timeout(&pool, 1).await.unwrap();
+        let e = sqlx::query("SELECT pg_sleep(1)").execute(&mut *tx).await.unwrap_err();
+        cancel.disarm();
+        let (status, Json(body)) = query_failed(e);
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body["code"], "query_timeout");
+    }
+}
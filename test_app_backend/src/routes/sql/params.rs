@@ -0,0 +1,263 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::postgres::{PgArguments, PgTypeInfo, PgTypeKind};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use sqlx::types::Uuid;
+use sqlx::{Arguments, Executor, PgPool, TypeInfo};
+
+/// A request parameter converted to the Rust type matching the Postgres type
+/// of its placeholder. `None` binds a typed SQL `NULL`.
+#[derive(Clone)]
+pub enum ParamValue {
+    Bool(Option<bool>),
+    Int2(Option<i16>),
+    Int4(Option<i32>),
+    Int8(Option<i64>),
+    Float4(Option<f32>),
+    Float8(Option<f64>),
+    Numeric(Option<Decimal>),
+    Text(Option<String>),
+    Date(Option<NaiveDate>),
+    Time(Option<NaiveTime>),
+    Timestamp(Option<NaiveDateTime>),
+    Timestamptz(Option<DateTime<Utc>>),
+    Uuid(Option<Uuid>),
+    Json(Option<Value>),
+}
+
+/// Why one parameter couldn't be bound; `index` is the `$n` it was meant for.
+#[derive(Serialize)]
+pub struct ParamIssue {
+    pub index: usize,
+    pub pg_type: String,
+    pub error: String,
+}
+
+pub enum ParamError {
+    /// The query uses a different number of placeholders than were given.
+    Count { expected: usize, given: usize },
+    Invalid(Vec<ParamIssue>),
+    /// Postgres couldn't prepare the query to infer the placeholder types.
+    Query(sqlx::Error),
+}
+
+/// Asks Postgres which type each `$n` in `query` has and converts `values`
+/// to match. Queries without placeholders or values skip the round trip.
+pub async fn resolve_params(
+    pool: &PgPool,
+    query: &str,
+    values: &[Value],
+) -> Result<Vec<ParamValue>, ParamError> {
+    if values.is_empty() && !has_placeholders(query) {
+        return Ok(vec![]);
+    }
+
+    let types = placeholder_types(pool, query).await.map_err(ParamError::Query)?;
+
+    if types.len() != values.len() {
+        return Err(ParamError::Count {
+            expected: types.len(),
+            given: values.len(),
+        });
+    }
+
+    let mut params = Vec::with_capacity(values.len());
+    let mut issues = vec![];
+    for (i, (value, type_info)) in values.iter().zip(&types).enumerate() {
+        match coerce(value, type_info) {
+            Ok(param) => params.push(param),
+            Err(error) => issues.push(ParamIssue {
+                index: i + 1,
+                pg_type: type_info.name().to_lowercase(),
+                error,
+            }),
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(params)
+    } else {
+        Err(ParamError::Invalid(issues))
+    }
+}
+
+/// Prepares `query` without running it and returns the type Postgres
+/// infers for each of its `$n` placeholders, in order.
+pub async fn placeholder_types(pool: &PgPool, query: &str) -> Result<Vec<PgTypeInfo>, sqlx::Error> {
+    let described = pool.describe(query).await?;
+    Ok(match described.parameters() {
+        Some(sqlx::Either::Left(types)) => types.to_vec(),
+        _ => vec![],
+    })
+}
+
+/// Encodes `params` in order as `$1..$n`.
+pub fn arguments(params: &[ParamValue]) -> Result<PgArguments, sqlx::Error> {
+    let mut args = PgArguments::default();
+    for param in params.iter().cloned() {
+        match param {
+            ParamValue::Bool(v) => args.add(v),
+            ParamValue::Int2(v) => args.add(v),
+            ParamValue::Int4(v) => args.add(v),
+            ParamValue::Int8(v) => args.add(v),
+            ParamValue::Float4(v) => args.add(v),
+            ParamValue::Float8(v) => args.add(v),
+            ParamValue::Numeric(v) => args.add(v),
+            ParamValue::Text(v) => args.add(v),
+            ParamValue::Date(v) => args.add(v),
+            ParamValue::Time(v) => args.add(v),
+            ParamValue::Timestamp(v) => args.add(v),
+            ParamValue::Timestamptz(v) => args.add(v),
+            ParamValue::Uuid(v) => args.add(v),
+            ParamValue::Json(v) => args.add(v),
+        }
+        .map_err(sqlx::Error::Encode)?;
+    }
+    Ok(args)
+}
+
+fn has_placeholders(query: &str) -> bool {
+    Tokenizer::new(&PostgreSqlDialect {}, query)
+        .tokenize()
+        .is_ok_and(|tokens| tokens.iter().any(|t| matches!(t, Token::Placeholder(_))))
+}
+
+fn coerce(value: &Value, type_info: &PgTypeInfo) -> Result<ParamValue, String> {
+    if let PgTypeKind::Domain(base) = type_info.kind() {
+        return coerce(value, base);
+    }
+
+    let param = match type_info.name().to_ascii_uppercase().as_str() {
+        "BOOL" => ParamValue::Bool(nullable(value, bool_value)?),
+        "INT2" => ParamValue::Int2(nullable(value, int_value)?),
+        "INT4" => ParamValue::Int4(nullable(value, int_value)?),
+        "INT8" => ParamValue::Int8(nullable(value, int_value)?),
+        "FLOAT4" => ParamValue::Float4(nullable(value, |v| float_value(v).map(|f| f as f32))?),
+        "FLOAT8" => ParamValue::Float8(nullable(value, float_value)?),
+        "NUMERIC" => ParamValue::Numeric(nullable(value, numeric_value)?),
+        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" | "UNKNOWN" => {
+            ParamValue::Text(nullable(value, text_value)?)
+        }
+        "DATE" => ParamValue::Date(nullable(value, |v| parse(v, "a date like 2024-01-31"))?),
+        "TIME" => ParamValue::Time(nullable(value, |v| parse(v, "a time like 13:45:00"))?),
+        "TIMESTAMP" => ParamValue::Timestamp(nullable(value, timestamp_value)?),
+        "TIMESTAMPTZ" => ParamValue::Timestamptz(nullable(value, timestamptz_value)?),
+        "UUID" => ParamValue::Uuid(nullable(value, |v| parse(v, "a UUID"))?),
+        "JSON" | "JSONB" => ParamValue::Json(nullable(value, |v| Ok(v.clone()))?),
+        other => {
+            return Err(format!(
+                "type {} is not supported as a parameter; cast the placeholder to text",
+                other.to_lowercase()
+            ))
+        }
+    };
+    Ok(param)
+}
+
+fn nullable<T>(
+    value: &Value,
+    convert: impl FnOnce(&Value) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    if value.is_null() {
+        Ok(None)
+    } else {
+        convert(value).map(Some)
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn bool_value(value: &Value) -> Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        Value::String(s) if s == "true" || s == "false" => Ok(s == "true"),
+        other => Err(format!("expected a boolean, got {}", kind(other))),
+    }
+}
+
+fn int_value<T: TryFrom<i64>>(value: &Value) -> Result<T, String> {
+    let n = match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("expected an integer, got {}", value))?;
+    T::try_from(n).map_err(|_| format!("{} is out of range", n))
+}
+
+fn float_value(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("expected a number, got {}", value))
+}
+
+/// Numbers are taken from their JSON text so a value like `19.99` doesn't
+/// pass through a float first.
+fn numeric_value(value: &Value) -> Result<Decimal, String> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        other => return Err(format!("expected a number, got {}", kind(other))),
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map_err(|_| format!("expected a number, got {}", value))
+}
+
+fn text_value(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        other => Err(format!("expected a string, got {}", kind(other))),
+    }
+}
+
+fn parse<T: FromStr>(value: &Value, expected: &str) -> Result<T, String> {
+    match value {
+        Value::String(s) => s
+            .trim()
+            .parse()
+            .map_err(|_| format!("expected {}, got {:?}", expected, s)),
+        other => Err(format!("expected {}, got {}", expected, kind(other))),
+    }
+}
+
+/// Accepts `2024-01-31T13:45:00`, `2024-01-31 13:45:00` or a bare date
+/// (midnight).
+fn timestamp_value(value: &Value) -> Result<NaiveDateTime, String> {
+    const EXPECTED: &str = "a timestamp like 2024-01-31 13:45:00";
+    let text = text_value(value).map_err(|_| format!("expected {}, got {}", EXPECTED, kind(value)))?;
+    let text = text.trim();
+
+    NaiveDateTime::from_str(text)
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f"))
+        .or_else(|_| NaiveDate::from_str(text).map(|d| d.and_time(NaiveTime::MIN)))
+        .map_err(|_| format!("expected {}, got {:?}", EXPECTED, text))
+}
+
+/// RFC 3339 timestamps keep their offset; anything `timestamp_value`
+/// accepts is taken as UTC.
+fn timestamptz_value(value: &Value) -> Result<DateTime<Utc>, String> {
+    if let Value::String(s) = value {
+        if let Ok(t) = DateTime::parse_from_rfc3339(s.trim()) {
+            return Ok(t.with_timezone(&Utc));
+        }
+    }
+    timestamp_value(value).map(|t| t.and_utc())
+}
@@ -0,0 +1,256 @@
+use axum::body::{Body, Bytes};
+use axum::http::header::{HeaderName, ACCEPT, CONTENT_TYPE};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::{Column, Describe, Executor, PgConnection, PgPool, Postgres, Row};
+use tokio::sync::mpsc;
+
+use super::audit::{Outcome, PendingAudit};
+use super::convert::pg_value_to_json;
+use super::params::{arguments, ParamValue};
+use super::timeout::begin_with_timeout;
+
+/// Row-at-a-time output formats selected through the `Accept` header.
+#[derive(Clone, Copy)]
+pub enum StreamFormat {
+    Ndjson,
+    Csv,
+}
+
+impl StreamFormat {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let accept = headers.get(ACCEPT)?.to_str().ok()?;
+        accept.split(',').find_map(|item| {
+            match item.split(';').next().unwrap_or("").trim() {
+                "application/x-ndjson" => Some(StreamFormat::Ndjson),
+                "text/csv" => Some(StreamFormat::Csv),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StreamFormat::Ndjson => "ndjson",
+            StreamFormat::Csv => "csv",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            StreamFormat::Ndjson => "application/x-ndjson",
+            StreamFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+/// Streams the rows of `sql` to the client as they arrive from Postgres.
+/// Errors raised before the first row become a regular JSON error response;
+/// errors after that abort the body. A client that disconnects, even while
+/// the statement has yet to produce a row, stops the producer, which
+/// cancels the backend statement. `audit` is finished once
+/// the last row has been sent.
+pub async fn stream_query(
+    pool: PgPool,
+    sql: String,
+    params: Vec<ParamValue>,
+    timeout_ms: u64,
+    row_limit: usize,
+    format: StreamFormat,
+    audit: PendingAudit,
+) -> Response {
+    let (sender, mut receiver) = mpsc::channel::<Result<Bytes, sqlx::Error>>(64);
+
+    tokio::spawn(async move {
+        match produce(&pool, &sql, &params, timeout_ms, format, &sender).await {
+            Ok(Some(rows)) => audit.finish(Outcome::Success, Some(rows as i64), None),
+            Ok(None) => audit.finish(
+                Outcome::Cancelled,
+                None,
+                Some(("client_disconnected", "Client disconnected mid-stream".to_string())),
+            ),
+            Err(e) => {
+                audit.failed(&e);
+                let _ = sender.send(Err(e)).await;
+            }
+        }
+    });
+
+    let first = match receiver.recv().await {
+        Some(Err(e)) => return super::query_error(e, timeout_ms).into_response(),
+        first => first,
+    };
+
+    let rest = stream::unfold(receiver, |mut rx| async move {
+        rx.recv().await.map(|item| (item, rx))
+    });
+    let body = Body::from_stream(stream::iter(first).chain(rest));
+
+    (
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (HeaderName::from_static("x-row-limit"), row_limit.to_string()),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Runs `sql` and sends its rows, returning how many were sent or `None`
+/// when the client went away first.
+async fn produce(
+    pool: &PgPool,
+    sql: &str,
+    params: &[ParamValue],
+    timeout_ms: u64,
+    format: StreamFormat,
+    sender: &mpsc::Sender<Result<Bytes, sqlx::Error>>,
+) -> Result<Option<usize>, sqlx::Error> {
+    let args = arguments(params)?;
+    let (mut tx, cancel) = begin_with_timeout(pool, timeout_ms).await?;
+
+    match send_rows(&mut tx, sql, args, format, sender).await {
+        // The statement may still be running for nobody; dropping the armed
+        // guard cancels it.
+        Ok(None) => Ok(None),
+        // Otherwise it has finished, and its connection will soon run
+        // someone else's query, so it must not be cancelled.
+        result => {
+            cancel.disarm();
+            tx.rollback().await?;
+            result
+        }
+    }
+}
+
+async fn send_rows(
+    tx: &mut PgConnection,
+    sql: &str,
+    args: PgArguments,
+    format: StreamFormat,
+    sender: &mpsc::Sender<Result<Bytes, sqlx::Error>>,
+) -> Result<Option<usize>, sqlx::Error> {
+    // Describe up front so an empty CSV still gets its header row. The
+    // header is held back until the first row (or the end of the stream) so
+    // errors raised by the first fetch still become a JSON error response.
+    let mut header = match format {
+        StreamFormat::Csv => Some(csv_header(&(&mut *tx).describe(sql).await?)),
+        StreamFormat::Ndjson => None,
+    };
+
+    let mut sent = 0;
+    {
+        let mut rows = sqlx::query_with(sql, args).fetch(&mut *tx);
+        loop {
+            // The receiver is gone once the client disconnects, which may
+            // be before the first row arrives.
+            let row = tokio::select! {
+                row = rows.try_next() => row?,
+                _ = sender.closed() => return Ok(None),
+            };
+            let Some(row) = row else { break };
+            let mut chunk = header.take().unwrap_or_default();
+
+            chunk.push_str(&match format {
+                StreamFormat::Ndjson => ndjson_line(&row),
+                StreamFormat::Csv => csv_line(&row),
+            });
+
+            if sender.send(Ok(Bytes::from(chunk))).await.is_err() {
+                return Ok(None);
+            }
+            sent += 1;
+        }
+    }
+
+    if let Some(header) = header {
+        let _ = sender.send(Ok(Bytes::from(header))).await;
+    }
+    Ok(Some(sent))
+}
+
+fn ndjson_line(row: &PgRow) -> String {
+    let object: serde_json::Map<String, serde_json::Value> = row
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.name().to_string(), pg_value_to_json(row, i)))
+        .collect();
+    let mut line = serde_json::Value::Object(object).to_string();
+    line.push('\n');
+    line
+}
+
+fn csv_header(described: &Describe<Postgres>) -> String {
+    let fields: Vec<String> = described.columns().iter().map(|c| csv_field(c.name())).collect();
+    fields.join(",") + "\r\n"
+}
+
+fn csv_line(row: &PgRow) -> String {
+    let fields: Vec<String> = (0..row.columns().len())
+        .map(|i| match pg_value_to_json(row, i) {
+            serde_json::Value::Null => String::new(),
+            serde_json::Value::String(s) => csv_field(&s),
+            other => csv_field(&other.to_string()),
+        })
+        .collect();
+    fields.join(",") + "\r\n"
+}
+
+/// Quotes a field per RFC 4180 when it contains a delimiter, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+    use crate::test_util;
+
+    #[tokio::test]
+    async fn failed_stream_leaves_the_connection_alone() {
+        let Some(pool) = test_util::pool().await else {
+            return;
+        };
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+
+        // Line up the next query for the failing query's connection while
+        // the other one is held, so anything trying to cancel the failed
+        // statement could only do so once the next query is running there.
+        let held = pool.acquire().await.unwrap();
+        let failing = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                let (sender, _receiver) = mpsc::channel(64);
+                let sql = "SELECT 1 / (random() * 0)::int FROM pg_sleep(0.1)";
+                produce(&pool, sql, &[], 5_000, StreamFormat::Ndjson, &sender).await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let next = tokio::spawn({
+            let pool = pool.clone();
+            async move { sqlx::query_scalar::<_, i32>("SELECT 1 FROM pg_sleep(0.3)").fetch_one(&pool).await }
+        });
+
+        let result = failing.await.unwrap();
+        assert!(matches!(result, Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("22012")));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        drop(held);
+
+        assert_eq!(next.await.unwrap().unwrap(), 1);
+    }
+}
@@ -0,0 +1,65 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// SQLSTATE Postgres reports when `statement_timeout` (or a cancel request)
+/// stops a running statement.
+const QUERY_CANCELED: &str = "57014";
+
+/// Opens a transaction on `pool` whose statements are cancelled by Postgres
+/// after `timeout_ms`. The returned guard cancels whatever the transaction is
+/// running if it is dropped before being disarmed, which is what happens
+/// when axum drops the handler future because the client went away.
+pub async fn begin_with_timeout(
+    pool: &PgPool,
+    timeout_ms: u64,
+) -> Result<(Transaction<'static, Postgres>, CancelOnDrop), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // set_config(..., true) is SET LOCAL, but lets the value be bound.
+    let (pid, _): (i32, String) =
+        sqlx::query_as("SELECT pg_backend_pid(), set_config('statement_timeout', $1, true)")
+            .bind(timeout_ms.to_string())
+            .fetch_one(&mut *tx)
+            .await?;
+
+    Ok((tx, CancelOnDrop { pool: pool.clone(), pid, armed: true }))
+}
+
+pub struct CancelOnDrop {
+    pool: PgPool,
+    pid: i32,
+    armed: bool,
+}
+
+impl CancelOnDrop {
+    /// Call once the statement has finished so dropping the guard is a no-op.
+    pub fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let pool = self.pool.clone();
+        let pid = self.pid;
+        tokio::spawn(async move {
+            let result = sqlx::query("SELECT pg_cancel_backend($1)")
+                .bind(pid)
+                .execute(&pool)
+                .await;
+            match result {
+                Ok(_) => tracing::info!("Cancelled abandoned query on backend {}", pid),
+                Err(e) => tracing::warn!("Failed to cancel query on backend {}: {}", pid, e),
+            }
+        });
+    }
+}
+
+pub fn is_timeout(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db) => db.code().as_deref() == Some(QUERY_CANCELED),
+        _ => false,
+    }
+}
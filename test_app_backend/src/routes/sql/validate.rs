@@ -0,0 +1,341 @@
+use std::ops::ControlFlow;
+
+use sqlparser::ast::{Expr, Query, Select, SetExpr, Spanned, Statement, Visit, Visitor};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+
+// Functions that write data or change server state even when called from a SELECT.
+const BLOCKED_FUNCTIONS: &[&str] = &[
+    "nextval", "setval", "set_config", "setseed",
+    "lo_create", "lo_creat", "lo_import", "lo_export", "lo_unlink", "lo_put", "lo_from_bytea",
+    "lo_open", "lo_close", "lowrite", "lo_truncate", "lo_truncate64",
+    "pg_notify", "pg_logical_emit_message",
+    "pg_terminate_backend", "pg_cancel_backend", "pg_reload_conf", "pg_rotate_logfile",
+    "pg_log_backend_memory_contexts", "pg_switch_wal", "pg_create_restore_point", "pg_promote",
+    "pg_backup_start", "pg_backup_stop", "pg_start_backup", "pg_stop_backup",
+    "pg_create_physical_replication_slot", "pg_create_logical_replication_slot",
+    "pg_drop_replication_slot", "pg_copy_physical_replication_slot", "pg_copy_logical_replication_slot",
+    "pg_replication_slot_advance", "pg_logical_slot_get_changes", "pg_logical_slot_get_binary_changes",
+    "pg_file_write", "pg_file_rename", "pg_file_unlink", "pg_file_sync",
+    "pg_stat_statements_reset",
+    "dblink", "dblink_exec", "dblink_connect", "dblink_connect_u", "dblink_send_query", "dblink_open",
+];
+
+// Whole families of the above: advisory locks and unlocks, statistics
+// resets and replication origin bookkeeping.
+const BLOCKED_PREFIXES: &[&str] = &["pg_advisory_", "pg_try_advisory_", "pg_stat_reset", "pg_replication_origin_"];
+
+/// Parses `query` as PostgreSQL and accepts it only if it is a single
+/// read-only query (SELECT, WITH ... SELECT, VALUES) with no
+/// data-modifying node anywhere in its tree.
+pub fn validate_readonly_sql(query: &str) -> Result<(), String> {
+    let dialect = PostgreSqlDialect {};
+
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize_with_location()
+        .map_err(|e| format!("Could not parse query: {}", e))?;
+
+    let statements = Parser::new(&dialect)
+        .with_tokens_with_locations(tokens.clone())
+        .parse_statements()
+        .map_err(|e| format!("Could not parse query: {}", e))?;
+
+    let statement = match statements.as_slice() {
+        [] => return Err("Empty query".to_string()),
+        [statement] => statement,
+        [_, _, ..] => {
+            let at = statement_start(&tokens, 1);
+            return Err(format!(
+                "Multiple statements are not allowed (second statement at {})",
+                describe(at)
+            ));
+        }
+    };
+
+    if !matches!(statement, Statement::Query(_)) {
+        return Err(format!(
+            "Only read-only queries are allowed, got {} at {}",
+            statement_kind(statement),
+            describe(statement_start(&tokens, 0))
+        ));
+    }
+
+    let mut guard = ReadOnlyGuard { tokens: &tokens, top_level: true };
+    match statement.visit(&mut guard) {
+        ControlFlow::Break(reason) => Err(reason),
+        ControlFlow::Continue(()) => Ok(()),
+    }
+}
+
+/// Walks the whole AST (CTEs, subqueries, set operations, expressions) and
+/// breaks with a message on the first clause that could modify data.
+struct ReadOnlyGuard<'a> {
+    tokens: &'a [TokenWithSpan],
+    top_level: bool,
+}
+
+impl Visitor for ReadOnlyGuard<'_> {
+    type Break = String;
+
+    fn pre_visit_statement(&mut self, statement: &Statement) -> ControlFlow<String> {
+        // The outer statement was already checked; anything below it is a
+        // statement embedded in a query, e.g. `WITH x AS (UPDATE ...) SELECT`.
+        if std::mem::take(&mut self.top_level) {
+            return ControlFlow::Continue(());
+        }
+        if matches!(statement, Statement::Query(_)) {
+            return ControlFlow::Continue(());
+        }
+        // Spans of embedded statements start at the target table, so step
+        // back to the statement keyword itself.
+        let keyword = match statement {
+            Statement::Insert(_) => Keyword::INSERT,
+            Statement::Update { .. } => Keyword::UPDATE,
+            Statement::Delete(_) => Keyword::DELETE,
+            _ => Keyword::NoKeyword,
+        };
+        let at = keyword_before(self.tokens, keyword, statement.span().start);
+        ControlFlow::Break(format!(
+            "{} is not allowed inside a query at {}",
+            statement_kind(statement),
+            describe(at)
+        ))
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<String> {
+        let from = query.span().start;
+
+        if let Some(select) = select_into(&query.body) {
+            let at = find_keyword(self.tokens, &[Keyword::INTO], select.span().start);
+            let into = select.into.as_ref().map(|into| into.name.to_string()).unwrap_or_default();
+            return ControlFlow::Break(format!("SELECT INTO ({}) is not allowed at {}", into, describe(at)));
+        }
+
+        if let Some(lock) = query.locks.first() {
+            let at = find_keyword(self.tokens, &[Keyword::FOR, lock_keyword(lock)], from);
+            return ControlFlow::Break(format!(
+                "Locking clause '{}' is not allowed at {}",
+                lock,
+                describe(at)
+            ));
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<String> {
+        if let Expr::Function(func) = expr {
+            let name = func
+                .name
+                .0
+                .last()
+                .map(|ident| ident.value.to_lowercase())
+                .unwrap_or_default();
+            if BLOCKED_FUNCTIONS.contains(&name.as_str()) || BLOCKED_PREFIXES.iter().any(|p| name.starts_with(p)) {
+                return ControlFlow::Break(format!(
+                    "Function '{}' is not allowed at {}",
+                    func.name,
+                    describe(Some(func.name.span().start))
+                ));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// The first SELECT with an INTO clause among the branches of `body`, e.g.
+/// the left side of `SELECT 1 INTO t UNION SELECT 2`. Parenthesized
+/// branches are queries of their own and are checked when visited.
+fn select_into(body: &SetExpr) -> Option<&Select> {
+    match body {
+        SetExpr::Select(select) if select.into.is_some() => Some(select),
+        SetExpr::SetOperation { left, right, .. } => select_into(left).or_else(|| select_into(right)),
+        _ => None,
+    }
+}
+
+fn statement_kind(statement: &Statement) -> String {
+    match statement {
+        Statement::Insert(_) => "INSERT".to_string(),
+        Statement::Update { .. } => "UPDATE".to_string(),
+        Statement::Delete(_) => "DELETE".to_string(),
+        Statement::Merge { .. } => "MERGE".to_string(),
+        Statement::Truncate { .. } => "TRUNCATE".to_string(),
+        Statement::Copy { .. } => "COPY".to_string(),
+        _ => {
+            // Fall back to the leading keywords, e.g. "CREATE TABLE" or "DROP".
+            let text = statement.to_string();
+            let words: Vec<&str> = text
+                .split_whitespace()
+                .take_while(|w| w.chars().all(|c| c.is_ascii_uppercase() || c == '_'))
+                .take(2)
+                .collect();
+            if words.is_empty() {
+                "a non-query statement".to_string()
+            } else {
+                words.join(" ")
+            }
+        }
+    }
+}
+
+fn lock_keyword(lock: &sqlparser::ast::LockClause) -> Keyword {
+    match lock.lock_type {
+        sqlparser::ast::LockType::Share => Keyword::SHARE,
+        sqlparser::ast::LockType::Update => Keyword::UPDATE,
+    }
+}
+
+/// Start of the `index`-th top-level statement: the first token after the
+/// `index`-th semicolon, skipping whitespace and comments.
+fn statement_start(tokens: &[TokenWithSpan], index: usize) -> Option<Location> {
+    let mut seen = 0;
+    tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .find(|t| {
+            if t.token == Token::SemiColon {
+                seen += 1;
+                return false;
+            }
+            seen == index
+        })
+        .map(|t| t.span.start)
+}
+
+/// Last occurrence of `keyword` strictly before `before`, falling back to
+/// `before` itself when the keyword cannot be found.
+fn keyword_before(tokens: &[TokenWithSpan], keyword: Keyword, before: Location) -> Option<Location> {
+    tokens
+        .iter()
+        .rev()
+        .filter(|t| t.span.start < before)
+        .find(|t| matches!(&t.token, Token::Word(w) if w.keyword == keyword))
+        .map(|t| t.span.start)
+        .or(Some(before))
+}
+
+/// Locates the first run of `keywords` (ignoring whitespace and comments)
+/// at or after `from`, so string literals never match.
+fn find_keyword(tokens: &[TokenWithSpan], keywords: &[Keyword], from: Location) -> Option<Location> {
+    let words: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .filter(|t| from.line == 0 || t.span.start >= from)
+        .collect();
+
+    words
+        .windows(keywords.len())
+        .find(|window| {
+            window.iter().zip(keywords).all(|(t, kw)| match &t.token {
+                Token::Word(w) => w.keyword == *kw,
+                _ => false,
+            })
+        })
+        .map(|window| window[0].span.start)
+}
+
+fn describe(location: Option<Location>) -> String {
+    match location {
+        Some(loc) if loc.line > 0 => format!("line {}, column {}", loc.line, loc.column),
+        _ => "unknown position".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(query: &str) -> String {
+        validate_readonly_sql(query).expect_err(query)
+    }
+
+    #[test]
+    fn rejects_multiple_statements() {
+        assert!(rejected("SELECT 1; SELECT 2").starts_with("Multiple statements are not allowed"));
+        assert!(rejected("SELECT 1; DELETE FROM vehicles").contains("line 1, column 11"));
+    }
+
+    #[test]
+    fn rejects_writes() {
+        assert!(rejected("DELETE FROM vehicles").starts_with("Only read-only queries are allowed, got DELETE"));
+        assert!(rejected("DROP TABLE vehicles").contains("DROP TABLE"));
+    }
+
+    #[test]
+    fn rejects_dml_inside_ctes() {
+        assert_eq!(
+            rejected("WITH x AS (UPDATE vehicles SET mileage = 0 RETURNING id) SELECT count(*) FROM x"),
+            "UPDATE is not allowed inside a query at line 1, column 12"
+        );
+        for query in [
+            "WITH x AS (INSERT INTO locations (name) VALUES ('a') RETURNING id) SELECT 1",
+            "SELECT * FROM (WITH x AS (UPDATE reviews SET rating = 1 RETURNING id) SELECT id FROM x) t",
+        ] {
+            assert!(rejected(query).contains("is not allowed inside a query"), "{}", query);
+        }
+        // The parser doesn't take DELETE in a CTE at all, which is as good.
+        assert!(rejected("WITH gone AS (DELETE FROM vehicles RETURNING id) SELECT * FROM gone")
+            .starts_with("Could not parse query"));
+    }
+
+    #[test]
+    fn rejects_select_into() {
+        assert!(rejected("SELECT * INTO copy FROM vehicles").starts_with("SELECT INTO (copy)"));
+        for query in [
+            "SELECT 1 AS a INTO newtab UNION SELECT 2",
+            "SELECT 1 UNION SELECT 2 AS a INTO newtab",
+            "SELECT 1 UNION SELECT 2 UNION ALL SELECT 3 INTO newtab",
+            "WITH t AS (SELECT 1) SELECT * INTO newtab FROM t",
+        ] {
+            assert!(rejected(query).starts_with("SELECT INTO (newtab)"), "{}", query);
+        }
+    }
+
+    #[test]
+    fn rejects_locking_clauses() {
+        for query in [
+            "SELECT * FROM vehicles FOR UPDATE",
+            "SELECT * FROM vehicles FOR SHARE",
+            "SELECT * FROM (SELECT * FROM vehicles FOR UPDATE) v",
+        ] {
+            assert!(rejected(query).starts_with("Locking clause"), "{}", query);
+        }
+        assert!(validate_readonly_sql("SELECT * FROM vehicles FOR NO KEY UPDATE").is_err());
+    }
+
+    #[test]
+    fn rejects_functions_with_side_effects() {
+        for query in [
+            "SELECT nextval('vehicles_id_seq')",
+            "SELECT pg_notify('c', 'x')",
+            "SELECT pg_catalog.pg_notify('c', 'x')",
+            "SELECT pg_advisory_lock_shared(1)",
+            "SELECT pg_advisory_xact_lock_shared(1)",
+            "SELECT pg_try_advisory_xact_lock(1)",
+            "SELECT pg_advisory_unlock_all()",
+            "SELECT pg_stat_reset()",
+            "SELECT id FROM vehicles WHERE set_config('role', 'postgres', false) IS NOT NULL",
+        ] {
+            assert!(rejected(query).starts_with("Function"), "{}", query);
+        }
+    }
+
+    #[test]
+    fn accepts_read_only_queries() {
+        for query in [
+            "SELECT * FROM vehicles",
+            "SELECT 'DELETE FROM vehicles; DROP TABLE x' AS text, 'INTO t' AS into_text",
+            "SELECT * FROM vehicles WHERE make = 'pg_notify(1)' -- UPDATE in a comment",
+            "VALUES (1, 'a'), (2, 'b')",
+            "WITH recent AS (SELECT * FROM reservations WHERE pickup_date > '2024-01-01') \
+             SELECT count(*) FROM recent",
+            "SELECT 1 UNION SELECT 2 EXCEPT SELECT 3",
+            "SELECT * FROM vehicles WHERE id = $1;",
+        ] {
+            assert_eq!(validate_readonly_sql(query), Ok(()), "{}", query);
+        }
+    }
+}
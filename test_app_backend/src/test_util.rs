@@ -0,0 +1,47 @@
+//! Shared setup for tests that need Postgres. They run against
+//! `DATABASE_URL` (and `SQL_READONLY_DATABASE_URL` where a read-only pool
+//! is needed, falling back to `DATABASE_URL`) and are skipped when it
+//! isn't set, so `cargo test` passes without a database.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::PgPool;
+
+use crate::config::{LlmConfig, SchemaConfig, SqlConfig};
+use crate::llm::LlmProvider;
+use crate::routes::{metrics, schema, sql, AppState};
+
+pub async fn pool() -> Option<PgPool> {
+    connect("DATABASE_URL").await
+}
+
+async fn connect(var: &str) -> Option<PgPool> {
+    let Ok(url) = std::env::var(var) else {
+        eprintln!("{} not set; skipping database test", var);
+        return None;
+    };
+    Some(PgPool::connect(&url).await.expect("Failed to connect to the test database"))
+}
+
+/// An `AppState` with default configuration and the given LLM provider.
+pub async fn state(llm: Arc<dyn LlmProvider>) -> Option<AppState> {
+    let pool = pool().await?;
+    let readonly_pool = match connect("SQL_READONLY_DATABASE_URL").await {
+        Some(readonly) => readonly,
+        None => pool.clone(),
+    };
+    let sql = SqlConfig::from_env();
+    Some(AppState {
+        sql_cursors: sql::CursorStore::new(Duration::from_secs(sql.cursor_ttl_secs)),
+        sql_audit: sql::AuditLog::spawn(pool.clone()),
+        sql_cache: sql::ResultCache::new(Duration::from_secs(sql.cache_ttl_secs), sql.cache_max_bytes),
+        schema_stats: schema::StatsCache::new(SchemaConfig::from_env()),
+        metrics: Arc::new(metrics::Catalog::from_env().expect("Invalid metrics catalog")),
+        llm,
+        llm_config: LlmConfig::from_env(),
+        sql,
+        pool,
+        readonly_pool,
+    })
+}
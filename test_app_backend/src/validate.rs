@@ -0,0 +1,134 @@
+//! Request body validation. Each `Create*` model lists its rules in a
+//! `Validate` impl; they mirror the schema's constraints so bad input is
+//! answered with a 422 naming every offending field before the database
+//! sees it.
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::error::{AppError, FieldError};
+
+pub trait Validate {
+    fn validate(&self, v: &mut Validator);
+}
+
+/// Collects the rules a value breaks. Rules on an absent optional field
+/// pass.
+#[derive(Default)]
+pub struct Validator {
+    issues: Vec<FieldError>,
+}
+
+/// Largest magnitude a `NUMERIC(10,2)` column holds.
+const MONEY_LIMIT: Decimal = Decimal::from_parts(100_000_000, 0, 0, false, 0);
+
+impl Validator {
+    /// Runs `value`'s rules, failing with every issue found.
+    pub fn check<T: Validate + ?Sized>(value: &T) -> Result<(), AppError> {
+        let mut v = Validator::default();
+        value.validate(&mut v);
+        if v.issues.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::validation(v.issues))
+        }
+    }
+
+    pub fn fail(&mut self, field: &str, message: impl Into<String>) {
+        // One message per field is enough to fix it.
+        if !self.issues.iter().any(|i| i.field == field) {
+            self.issues.push(FieldError::new(field, message));
+        }
+    }
+
+    pub fn rule(&mut self, field: &str, ok: bool, message: &str) -> &mut Self {
+        if !ok {
+            self.fail(field, message);
+        }
+        self
+    }
+
+    /// A `NOT NULL VARCHAR(max)` column: not blank, at most `max` characters.
+    pub fn required(&mut self, field: &str, value: &str, max: usize) -> &mut Self {
+        if value.trim().is_empty() {
+            self.fail(field, "must not be blank");
+        }
+        self.max_len(field, value, max)
+    }
+
+    /// At most `max` characters, as `VARCHAR(max)` counts them.
+    pub fn max_len<'a>(&mut self, field: &str, value: impl Into<Option<&'a str>>, max: usize) -> &mut Self {
+        if value.into().is_some_and(|s| s.chars().count() > max) {
+            self.fail(field, format!("must be at most {} characters", max));
+        }
+        self
+    }
+
+    pub fn range(&mut self, field: &str, value: impl Into<Option<i32>>, min: i32, max: i32) -> &mut Self {
+        if value.into().is_some_and(|n| !(min..=max).contains(&n)) {
+            self.fail(field, format!("must be between {} and {}", min, max));
+        }
+        self
+    }
+
+    pub fn at_least(&mut self, field: &str, value: impl Into<Option<i32>>, min: i32) -> &mut Self {
+        if value.into().is_some_and(|n| n < min) {
+            self.fail(field, format!("must be at least {}", min));
+        }
+        self
+    }
+
+    /// A non-negative amount that fits `NUMERIC(10,2)`.
+    pub fn money(&mut self, field: &str, value: impl Into<Option<Decimal>>) -> &mut Self {
+        match value.into() {
+            Some(d) if d < Decimal::ZERO => self.fail(field, "must not be negative"),
+            Some(d) if d.normalize().scale() > 2 => self.fail(field, "must have at most 2 decimal places"),
+            Some(d) if d.abs() >= MONEY_LIMIT => self.fail(field, format!("must be less than {}", MONEY_LIMIT)),
+            _ => {}
+        }
+        self
+    }
+
+    pub fn email<'a>(&mut self, field: &str, value: impl Into<Option<&'a str>>) -> &mut Self {
+        if value.into().is_some_and(|s| !is_email(s)) {
+            self.fail(field, "must be a valid email address");
+        }
+        self
+    }
+
+    pub fn phone<'a>(&mut self, field: &str, value: impl Into<Option<&'a str>>) -> &mut Self {
+        if value.into().is_some_and(|s| !is_phone(s)) {
+            self.fail(field, "must be a valid phone number");
+        }
+        self
+    }
+
+    /// `field` must not fall before `other`, e.g. a return date before the
+    /// pickup date.
+    pub fn not_before(&mut self, field: &str, date: NaiveDate, other: &str, other_date: NaiveDate) -> &mut Self {
+        if date < other_date {
+            self.fail(field, format!("must not be before {}", other));
+        }
+        self
+    }
+}
+
+/// `local@domain.tld`, without spaces. Deliverability isn't our problem.
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !s.chars().any(char::is_whitespace)
+        && !domain.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|part| !part.is_empty())
+}
+
+/// Digits with the usual separators, e.g. `(212) 555-0101` or
+/// `+1 212.555.0101`.
+fn is_phone(s: &str) -> bool {
+    let digits = s.chars().filter(char::is_ascii_digit).count();
+    let body = s.strip_prefix('+').unwrap_or(s);
+    (7..=15).contains(&digits) && body.chars().all(|c| c.is_ascii_digit() || " -.()".contains(c))
+}
@@ -0,0 +1,12 @@
+-- Named read-only queries analysts can rerun through /api/saved-queries
+CREATE TABLE saved_queries (
+    id               SERIAL PRIMARY KEY,
+    name             VARCHAR(100) NOT NULL UNIQUE,
+    description      TEXT,
+    query            TEXT NOT NULL,
+    params           JSONB NOT NULL DEFAULT '[]',
+    created_at       TIMESTAMP DEFAULT NOW(),
+    updated_at       TIMESTAMP DEFAULT NOW(),
+    last_run_at      TIMESTAMP,
+    last_duration_ms DOUBLE PRECISION
+);
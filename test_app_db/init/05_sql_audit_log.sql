@@ -0,0 +1,21 @@
+-- Every statement submitted to /api/sql, written by the backend's writable pool
+CREATE TABLE sql_audit_log (
+    id           BIGSERIAL PRIMARY KEY,
+    executed_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    caller       VARCHAR(255) NOT NULL,
+    query        TEXT NOT NULL,
+    query_hash   CHAR(64) NOT NULL,
+    params       JSONB NOT NULL DEFAULT '[]',
+    mode         VARCHAR(20) NOT NULL,
+    outcome      VARCHAR(20) NOT NULL CHECK (outcome IN ('success','rejected','failed','timeout','cancelled')),
+    error_code   VARCHAR(50),
+    error        TEXT,
+    row_count    BIGINT,
+    duration_ms  DOUBLE PRECISION NOT NULL
+);
+
+CREATE INDEX idx_sql_audit_executed_at ON sql_audit_log(executed_at);
+CREATE INDEX idx_sql_audit_caller      ON sql_audit_log(caller, executed_at);
+
+-- The SQL runner shouldn't be able to read back what it (or anyone) ran
+REVOKE ALL ON sql_audit_log FROM readonly_user;
@@ -10,6 +10,16 @@ const api = {
         return res.json();
     },
 
+    // For paged list endpoints: the rows plus how many match in total.
+    async getPage(path) {
+        const res = await fetch(`${API_URL}${path}`);
+        if (!res.ok) {
+            const err = await res.text();
+            throw new Error(`GET ${path}: ${res.status} ${err}`);
+        }
+        return { rows: await res.json(), total: Number(res.headers.get('X-Total-Count') || 0) };
+    },
+
     async post(path, data) {
         const res = await fetch(`${API_URL}${path}`, {
             method: 'POST',
@@ -18,7 +18,7 @@ const TABLES = {
             { key: 'location_id', label: 'Location ID', type: 'number' },
             { key: 'first_name', label: 'First Name', type: 'text' },
             { key: 'last_name', label: 'Last Name', type: 'text' },
-            { key: 'role', label: 'Role', type: 'select', options: ['manager','agent','mechanic','receptionist'] },
+            { key: 'role', label: 'Role', type: 'select' },
             { key: 'salary', label: 'Salary', type: 'decimal' },
             { key: 'hire_date', label: 'Hire Date', type: 'date' },
             { key: 'email', label: 'Email', type: 'text' },
@@ -50,7 +50,7 @@ const TABLES = {
             { key: 'color', label: 'Color', type: 'text' },
             { key: 'daily_rate', label: 'Daily Rate', type: 'decimal' },
             { key: 'mileage', label: 'Mileage', type: 'number' },
-            { key: 'status', label: 'Status', type: 'select', options: ['available','rented','maintenance','retired'] },
+            { key: 'status', label: 'Status', type: 'select' },
         ],
     },
     clients: {
@@ -78,7 +78,7 @@ const TABLES = {
             { key: 'return_location', label: 'Return Loc', type: 'number' },
             { key: 'pickup_date', label: 'Pickup', type: 'date' },
             { key: 'return_date', label: 'Return', type: 'date' },
-            { key: 'status', label: 'Status', type: 'select', options: ['confirmed','active','completed','cancelled','no_show'] },
+            { key: 'status', label: 'Status', type: 'select' },
             { key: 'total_cost', label: 'Total Cost', type: 'decimal' },
         ],
     },
@@ -89,9 +89,9 @@ const TABLES = {
             { key: 'id', label: 'ID' },
             { key: 'reservation_id', label: 'Reservation ID', type: 'number' },
             { key: 'amount', label: 'Amount', type: 'decimal' },
-            { key: 'payment_method', label: 'Method', type: 'select', options: ['credit_card','debit_card','cash'] },
+            { key: 'payment_method', label: 'Method', type: 'select' },
             { key: 'payment_date', label: 'Date', type: 'date' },
-            { key: 'status', label: 'Status', type: 'select', options: ['completed','pending','refunded','failed'] },
+            { key: 'status', label: 'Status', type: 'select' },
         ],
     },
     maintenance: {
@@ -100,7 +100,7 @@ const TABLES = {
         columns: [
             { key: 'id', label: 'ID' },
             { key: 'vehicle_id', label: 'Vehicle ID', type: 'number' },
-            { key: 'maintenance_type', label: 'Type', type: 'select', options: ['oil_change','tire_rotation','brake_service','general_inspection','engine_repair','body_repair','transmission','ac_service','battery_replacement'] },
+            { key: 'maintenance_type', label: 'Type', type: 'select' },
             { key: 'description', label: 'Description', type: 'text' },
             { key: 'cost', label: 'Cost', type: 'decimal' },
             { key: 'maintenance_date', label: 'Date', type: 'date' },
@@ -124,13 +124,15 @@ const TABLES = {
 const PAGE_SIZE = 25;
 let currentPage = 0;
 let currentData = [];
-let filteredData = [];
+let totalRows = 0;
 let currentConfig = null;
 let sortKey = null;
 let sortDir = 'asc';
 let searchQuery = '';
+let searchField = null;
 let advancedFilters = {}; // { key: { min, max } } for number/decimal/date, { key: value } for select
 let advancedOpen = false;
+let searchTimer = null;
 
 async function renderTable(container, config) {
     currentConfig = config;
@@ -138,69 +140,67 @@ async function renderTable(container, config) {
     sortKey = null;
     sortDir = 'asc';
     searchQuery = '';
+    searchField = searchColumns()[0]?.key ?? null;
     advancedFilters = {};
     advancedOpen = false;
     container.innerHTML = '<div class="loading">Loading...</div>';
+    await loadPage(container);
+}
 
+// Paging, sorting, filtering and search all happen on the server; see the
+// list parameters in test_app_backend/src/routes/list.rs.
+function buildListQuery() {
+    const params = new URLSearchParams();
+    params.set('limit', PAGE_SIZE);
+    params.set('offset', currentPage * PAGE_SIZE);
+    if (sortKey) params.set('sort', (sortDir === 'desc' ? '-' : '') + sortKey);
+    if (searchField && searchQuery.trim()) params.set(`${searchField}[contains]`, searchQuery.trim());
+
+    for (const [key, filter] of Object.entries(advancedFilters)) {
+        const col = currentConfig.columns.find(c => c.key === key);
+        if (!col) continue;
+        if (col.type === 'select' || col.type === 'boolean') {
+            if (filter.value) params.set(key, filter.value);
+        } else {
+            if (filter.min != null && filter.min !== '') params.set(`${key}[gte]`, filter.min);
+            if (filter.max != null && filter.max !== '') params.set(`${key}[lte]`, filter.max);
+        }
+    }
+    return params.toString();
+}
+
+// Choices for enum columns come from the server, so the dropdowns match
+// what the API accepts. Fetched once and filled into TABLES.
+let enumsLoaded = false;
+
+async function loadEnumOptions() {
+    if (enumsLoaded) return;
+    const enums = await api.get('/api/enums');
+    for (const [table, fields] of Object.entries(enums)) {
+        for (const [key, values] of Object.entries(fields)) {
+            const col = TABLES[table]?.columns.find(c => c.key === key);
+            if (col) col.options = values;
+        }
+    }
+    enumsLoaded = true;
+}
+
+async function loadPage(container) {
     try {
-        currentData = await api.get(config.endpoint);
+        await loadEnumOptions();
+        const page = await api.getPage(`${currentConfig.endpoint}?${buildListQuery()}`);
+        currentData = page.rows;
+        totalRows = page.total;
     } catch (e) {
         container.innerHTML = `<div class="loading" style="color:var(--danger)">Error: ${e.message}</div>`;
         return;
     }
-
-    applyFiltersAndSort();
     renderPage(container);
 }
 
-function applyFiltersAndSort() {
-    const q = searchQuery.toLowerCase().trim();
-
-    filteredData = currentData.filter(row => {
-        // global text search across all columns
-        if (q) {
-            const match = Object.values(row).some(v =>
-                String(v ?? '').toLowerCase().includes(q)
-            );
-            if (!match) return false;
-        }
-
-        // advanced filters
-        for (const [key, filter] of Object.entries(advancedFilters)) {
-            const col = currentConfig.columns.find(c => c.key === key);
-            if (!col) continue;
-            const val = row[key];
-
-            if (col.type === 'select' || col.type === 'boolean') {
-                if (filter.value && String(val) !== filter.value) return false;
-            } else if (col.type === 'date') {
-                if (filter.min && String(val || '') < filter.min) return false;
-                if (filter.max && String(val || '') > filter.max) return false;
-            } else if (col.type === 'number' || col.type === 'decimal') {
-                const num = Number(val);
-                if (filter.min !== '' && filter.min != null && num < Number(filter.min)) return false;
-                if (filter.max !== '' && filter.max != null && num > Number(filter.max)) return false;
-            }
-        }
-
-        return true;
-    });
-
-    if (sortKey) {
-        filteredData.sort((a, b) => {
-            let va = a[sortKey], vb = b[sortKey];
-            if (va == null) va = '';
-            if (vb == null) vb = '';
-            const na = Number(va), nb = Number(vb);
-            if (!isNaN(na) && !isNaN(nb) && va !== '' && vb !== '') {
-                return sortDir === 'asc' ? na - nb : nb - na;
-            }
-            const sa = String(va).toLowerCase(), sb = String(vb).toLowerCase();
-            if (sa < sb) return sortDir === 'asc' ? -1 : 1;
-            if (sa > sb) return sortDir === 'asc' ? 1 : -1;
-            return 0;
-        });
-    }
+async function reload() {
+    const container = document.getElementById('content');
+    await loadPage(container);
 }
 
 function handleSort(key) {
@@ -211,18 +211,30 @@ function handleSort(key) {
         sortDir = 'asc';
     }
     currentPage = 0;
-    applyFiltersAndSort();
-    renderPage(document.getElementById('content'));
+    reload();
+}
+
+// Text columns, the only ones the server can search.
+function searchColumns() {
+    return currentConfig.columns.filter(c => c.type === 'text');
 }
 
 function handleSearch(value) {
     searchQuery = value;
     currentPage = 0;
-    applyFiltersAndSort();
-    renderPage(document.getElementById('content'));
-    // restore focus and cursor position
-    const el = document.getElementById('global-search');
-    if (el) { el.focus(); el.selectionStart = el.selectionEnd = value.length; }
+    clearTimeout(searchTimer);
+    searchTimer = setTimeout(async () => {
+        await reload();
+        // restore focus and cursor position
+        const el = document.getElementById('global-search');
+        if (el) { el.focus(); el.selectionStart = el.selectionEnd = searchQuery.length; }
+    }, 250);
+}
+
+function handleSearchField(key) {
+    searchField = key;
+    currentPage = 0;
+    if (searchQuery.trim()) reload();
 }
 
 function toggleAdvanced() {
@@ -234,16 +246,14 @@ function updateAdvancedFilter(key, field, value) {
     if (!advancedFilters[key]) advancedFilters[key] = {};
     advancedFilters[key][field] = value;
     currentPage = 0;
-    applyFiltersAndSort();
-    renderPage(document.getElementById('content'));
+    reload();
 }
 
 function clearAllFilters() {
     searchQuery = '';
     advancedFilters = {};
     currentPage = 0;
-    applyFiltersAndSort();
-    renderPage(document.getElementById('content'));
+    reload();
 }
 
 function hasActiveFilters() {
@@ -331,14 +341,14 @@ function buildAdvancedPanel() {
 
 function renderPage(container) {
     const config = currentConfig;
-    const totalPages = Math.ceil(filteredData.length / PAGE_SIZE);
-    const pageData = filteredData.slice(currentPage * PAGE_SIZE, (currentPage + 1) * PAGE_SIZE);
+    const totalPages = Math.ceil(totalRows / PAGE_SIZE);
+    const pageData = currentData;
+    const searchCols = searchColumns();
+    const searchLabel = searchCols.find(c => c.key === searchField)?.label ?? '';
 
     const visibleCols = config.columns.filter(c => c.key !== 'created_at');
 
-    const countLabel = filteredData.length !== currentData.length
-        ? `${filteredData.length} / ${currentData.length}`
-        : `${currentData.length}`;
+    const countLabel = `${totalRows}`;
 
     const activeFilters = hasActiveFilters();
     const advChevron = advancedOpen ? '&#9650;' : '&#9660;';
@@ -350,7 +360,11 @@ function renderPage(container) {
         </div>
         <div class="filter-bar">
             <div class="filter-bar-main">
-                <input type="text" id="global-search" class="search-input" placeholder="Search all columns..." value="${escHtml(searchQuery)}" oninput="handleSearch(this.value)">
+                ${searchCols.length ? `
+                <select class="search-field" onchange="handleSearchField(this.value)" title="Column to search">
+                    ${searchCols.map(c => `<option value="${c.key}" ${c.key === searchField ? 'selected' : ''}>${c.label}</option>`).join('')}
+                </select>
+                <input type="text" id="global-search" class="search-input" placeholder="Search ${escHtml(searchLabel.toLowerCase())}..." value="${escHtml(searchQuery)}" oninput="handleSearch(this.value)">` : ''}
                 <button class="btn btn-secondary btn-sm adv-toggle ${advancedOpen ? 'adv-toggle-active' : ''}" onclick="toggleAdvanced()">Advanced ${advChevron}</button>
                 ${activeFilters ? `<button class="btn btn-danger btn-sm" onclick="clearAllFilters()">Clear filters</button>` : ''}
             </div>
@@ -398,12 +412,12 @@ function escHtml(str) {
 }
 
 function prevPage() {
-    if (currentPage > 0) { currentPage--; renderPage(document.getElementById('content')); }
+    if (currentPage > 0) { currentPage--; reload(); }
 }
 
 function nextPage() {
-    const totalPages = Math.ceil(filteredData.length / PAGE_SIZE);
-    if (currentPage < totalPages - 1) { currentPage++; renderPage(document.getElementById('content')); }
+    const totalPages = Math.ceil(totalRows / PAGE_SIZE);
+    if (currentPage < totalPages - 1) { currentPage++; reload(); }
 }
 
 // ── Modal CRUD ──────────────────────────────────
@@ -480,7 +494,7 @@ async function saveNew() {
     try {
         await api.post(currentConfig.endpoint, getFormData());
         closeModal();
-        await renderTable(document.getElementById('content'), currentConfig);
+        await reload();
     } catch (e) { alert('Error: ' + e.message); }
 }
 
@@ -488,7 +502,7 @@ async function saveEdit(id) {
     try {
         await api.put(`${currentConfig.endpoint}/${id}`, getFormData());
         closeModal();
-        await renderTable(document.getElementById('content'), currentConfig);
+        await reload();
     } catch (e) { alert('Error: ' + e.message); }
 }
 
@@ -496,6 +510,6 @@ async function deleteRow(id) {
     if (!confirm(`Delete ${currentConfig.label.replace(/s$/,'')} #${id}?`)) return;
     try {
         await api.del(`${currentConfig.endpoint}/${id}`);
-        await renderTable(document.getElementById('content'), currentConfig);
+        await reload();
     } catch (e) { alert('Error: ' + e.message); }
 }